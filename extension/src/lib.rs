@@ -16,7 +16,7 @@ impl EnvChecker {
         }
 
         if let Some(path) = &self.cached_binary_path {
-            if fs::metadata(path).map_or(false, |stat| stat.is_file()) {
+            if fs::metadata(path).is_ok_and(|stat| stat.is_file()) {
                 return Ok(path.clone());
             }
         }
@@ -51,7 +51,7 @@ impl EnvChecker {
             .expect("Could not convert binary path to str")
             .to_string();
 
-        if !fs::metadata(&binary_path).map_or(false, |stat| stat.is_file()) {
+        if !fs::metadata(&binary_path).is_ok_and(|stat| stat.is_file()) {
             zed::set_language_server_installation_status(
                 language_server_id,
                 &zed::LanguageServerInstallationStatus::Downloading,
@@ -115,7 +115,7 @@ impl EnvChecker {
             .to_string()
     }
 
-    fn clean_other_installations(version_to_keep: &String) -> Result<(), String> {
+    fn clean_other_installations(version_to_keep: &str) -> Result<(), String> {
         let entries =
             fs::read_dir(".").map_err(|e| format!("failed to list working directory {e}"))?;
         for entry in entries {
@@ -0,0 +1,461 @@
+use crate::config;
+use crate::diagnostics;
+use crate::drift::SchemaConflict;
+use crate::env_file;
+use crate::schema;
+use crate::secrets::{self, SecretLeak};
+use crate::types::{Config, EnvFileEntry, EnvVar, ParsedSchema, Rules, SchemaSource};
+use crate::validation::{self, ValidationResult};
+use crate::workspace;
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+/// A stack of env files validated together, either a configured profile or
+/// the merged env files of one package when no profiles are configured.
+#[derive(Debug)]
+pub struct CheckTarget {
+    pub profile: Option<String>,
+    pub files: Vec<String>,
+    /// The variables of the schemas that apply to `files`.
+    pub schema_vars: Vec<EnvVar>,
+    pub result: ValidationResult,
+}
+
+#[derive(Debug)]
+pub struct CheckReport {
+    pub workspace_root: PathBuf,
+    pub schema_sources: Vec<SchemaSource>,
+    pub schema_vars: Vec<EnvVar>,
+    pub targets: Vec<CheckTarget>,
+    pub leaks: Vec<SecretLeak>,
+    pub conflicts: Vec<SchemaConflict>,
+    /// Where the variables came from when they were not read from env files,
+    /// see [`check_environment`].
+    pub environment: Option<String>,
+}
+
+/// A diagnostic together with the env file it belongs to and the schema
+/// variable it is about, if any.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub file_path: String,
+    pub diagnostic: Diagnostic,
+    pub var_name: String,
+    pub schema_var: Option<EnvVar>,
+    pub profile: Option<String>,
+}
+
+impl Finding {
+    pub fn rule_id(&self) -> &str {
+        match &self.diagnostic.code {
+            Some(NumberOrString::String(code)) => code,
+            _ => "env-checker",
+        }
+    }
+}
+
+/// Validates a workspace without an editor: discovers schemas, loads the env
+/// files of every profile (or all env files) and checks them against the
+/// schemas of their package.
+///
+/// `profiles` restricts the check to the named profiles; an empty slice
+/// checks all of them.
+pub fn check_workspace(workspace_root: &Path, config: &Config, profiles: &[String]) -> Result<CheckReport> {
+    for profile in profiles {
+        if !config.profiles.contains_key(profile) {
+            bail!("Unknown profile '{}'", profile);
+        }
+    }
+
+    let schema_sources = config::discover_schemas(workspace_root, config)?;
+    let schemas = schema::parse_schemas(&schema_sources);
+    let mut schema_vars = schema::merge_schemas(&schemas);
+    config::apply_required_overrides(config, &mut schema_vars);
+
+    // Variables and rules of the schemas that apply to a set of env files,
+    // resolved from the first one.
+    let schema_for = |files: &[String]| {
+        let probe = files
+            .first()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| workspace_root.join(".env"));
+        scoped_schema(&schemas, &probe, workspace_root, config)
+    };
+
+    let targets: Vec<CheckTarget> = if config.profiles.is_empty() {
+        let mut packages: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
+        for file in config::get_env_file_paths(workspace_root, config)? {
+            packages
+                .entry(workspace::package_root(Path::new(&file), workspace_root))
+                .or_default()
+                .push(file);
+        }
+        if packages.is_empty() {
+            packages.insert(workspace_root.to_path_buf(), Vec::new());
+        }
+
+        packages
+            .into_values()
+            .map(|files| {
+                let layers = files
+                    .iter()
+                    .filter_map(|path| env_file::parse_env_file(Path::new(path)).ok())
+                    .collect();
+                let entries = env_file::merge_env_files(layers);
+                let (vars, rules) = schema_for(&files);
+
+                CheckTarget {
+                    profile: None,
+                    result: validation::validate(&vars, &rules, &entries, None),
+                    schema_vars: vars,
+                    files,
+                }
+            })
+            .collect()
+    } else {
+        config
+            .profiles
+            .keys()
+            .filter(|name| profiles.is_empty() || profiles.contains(name))
+            .map(|name| {
+                let files = config::get_profile_file_paths(workspace_root, config, name);
+                let entries = env_file::load_layered_env_files(&files);
+                let (vars, rules) = schema_for(&files);
+
+                CheckTarget {
+                    profile: Some(name.clone()),
+                    result: validation::validate(&vars, &rules, &entries, Some(name)),
+                    schema_vars: vars,
+                    files,
+                }
+            })
+            .collect()
+    };
+
+    let mut scanned = HashSet::new();
+    let mut leaks = Vec::new();
+    let example_path = workspace_root.join(".env.example").to_string_lossy().to_string();
+    let files = targets.iter().flat_map(|t| t.files.iter()).chain(std::iter::once(&example_path));
+
+    for file in files {
+        if !scanned.insert(file.clone()) {
+            continue;
+        }
+        if let Ok(entries) = env_file::parse_env_file(Path::new(file)) {
+            leaks.extend(secrets::find_leaked_secrets(&entries, &schema_vars, Path::new(file)));
+        }
+    }
+
+    Ok(CheckReport {
+        workspace_root: workspace_root.to_path_buf(),
+        schema_sources,
+        schema_vars,
+        targets,
+        leaks,
+        conflicts: workspace::find_package_conflicts(&schemas, workspace_root),
+        environment: None,
+    })
+}
+
+/// Validates variables that do not come from env files, such as the
+/// environment of the current process, against the workspace schemas.
+///
+/// Only variables declared in a schema are considered, so unrelated process
+/// variables like `PATH` are not reported as unused. `profile` names the
+/// environment being checked, for variables required only in some profiles.
+pub fn check_environment(
+    workspace_root: &Path,
+    config: &Config,
+    entries: Vec<EnvFileEntry>,
+    source: &str,
+    profile: Option<&str>,
+) -> Result<CheckReport> {
+    let schema_sources = config::discover_schemas(workspace_root, config)?;
+    let schemas = schema::parse_schemas(&schema_sources);
+    let mut schema_vars = schema::merge_schemas(&schemas);
+    config::apply_required_overrides(config, &mut schema_vars);
+
+    // Besides configured profiles, those that variables are required in can
+    // be checked without configuring their env files.
+    if let Some(profile) = profile {
+        let known = config.profiles.contains_key(profile)
+            || schema_vars.iter().any(|v| v.required_in.iter().any(|p| p == profile));
+        if !known {
+            bail!("Unknown profile '{}'", profile);
+        }
+    }
+
+    // The environment stands in for the env files at the workspace root.
+    let (vars, rules) = scoped_schema(&schemas, &workspace_root.join(".env"), workspace_root, config);
+
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|e| vars.iter().any(|v| v.name == e.name) || rules.mentions(&e.name))
+        .collect();
+
+    let target = CheckTarget {
+        profile: profile.map(String::from),
+        files: vec![source.to_string()],
+        result: validation::validate(&vars, &rules, &entries, profile),
+        schema_vars: vars,
+    };
+
+    Ok(CheckReport {
+        workspace_root: workspace_root.to_path_buf(),
+        schema_sources,
+        schema_vars,
+        targets: vec![target],
+        leaks: Vec::new(),
+        conflicts: workspace::find_package_conflicts(&schemas, workspace_root),
+        environment: Some(source.to_string()),
+    })
+}
+
+/// The variables and rules of the schemas that apply to `file_path`, with
+/// the configured requirements and rules added.
+fn scoped_schema(schemas: &[ParsedSchema], file_path: &Path, workspace_root: &Path, config: &Config) -> (Vec<EnvVar>, Rules) {
+    let scoped: Vec<ParsedSchema> = workspace::scoped_schemas(schemas, file_path, workspace_root)
+        .into_iter()
+        .cloned()
+        .collect();
+    let mut vars = schema::merge_schemas(&scoped);
+    config::apply_required_overrides(config, &mut vars);
+    let mut rules = schema::merge_rules(&scoped);
+    rules.extend(&config.rules);
+    (vars, rules)
+}
+
+impl CheckReport {
+    pub fn has_errors(&self) -> bool {
+        self.findings()
+            .iter()
+            .any(|f| f.diagnostic.severity == Some(DiagnosticSeverity::ERROR))
+    }
+
+    /// Converts the report into diagnostics, using the same messages as the
+    /// language server. Findings shared by several profiles are reported once.
+    pub fn findings(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut seen = HashSet::new();
+
+        for target in &self.targets {
+            for finding in self.target_findings(target) {
+                push_unique(&mut findings, &mut seen, finding);
+            }
+        }
+
+        for finding in self.workspace_findings() {
+            push_unique(&mut findings, &mut seen, finding);
+        }
+
+        findings
+    }
+
+    /// The findings that do not belong to a single target: secret leaks and
+    /// conflicts between schemas.
+    pub fn workspace_findings(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut seen = HashSet::new();
+
+        for leak in &self.leaks {
+            push_unique(&mut findings, &mut seen, Finding {
+                file_path: leak.entry.file_path.clone(),
+                diagnostic: diagnostics::create_secret_leak_diagnostic(leak),
+                var_name: leak.entry.name.clone(),
+                schema_var: self.schema_vars.iter().find(|v| v.name == leak.entry.name).cloned(),
+                profile: None,
+            });
+        }
+
+        for conflict in &self.conflicts {
+            push_unique(&mut findings, &mut seen, Finding {
+                file_path: conflict.second_location().source.file_path().to_string(),
+                diagnostic: diagnostics::create_schema_conflict_diagnostic(conflict, true),
+                var_name: conflict.name.clone(),
+                schema_var: Some(conflict.first.clone()),
+                profile: None,
+            });
+        }
+
+        findings
+    }
+
+    /// The validation findings of a single target, without secret leaks.
+    pub fn target_findings(&self, target: &CheckTarget) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let top_file = target.files.last().cloned().unwrap_or_else(|| {
+            self.workspace_root.join(".env").to_string_lossy().to_string()
+        });
+        let profiles: Vec<String> = target.profile.iter().cloned().collect();
+
+        for error in &target.result.errors {
+            let (file_path, diagnostic) = match &error.entry {
+                None => (top_file.clone(), diagnostics::create_missing_var_diagnostic(error, &profiles)),
+                Some(entry) => (entry.file_path.clone(), diagnostics::create_entry_diagnostic(error)),
+            };
+            findings.push(Finding {
+                file_path,
+                diagnostic,
+                var_name: error.var_name.clone(),
+                schema_var: Some(error.schema_var.clone()),
+                profile: target.profile.clone(),
+            });
+        }
+
+        for warning in &target.result.warnings {
+            findings.push(Finding {
+                file_path: warning.entry.as_ref().map_or(top_file.clone(), |e| e.file_path.clone()),
+                diagnostic: diagnostics::create_deprecated_var_diagnostic(warning),
+                var_name: warning.var_name.clone(),
+                schema_var: Some(warning.schema_var.clone()),
+                profile: target.profile.clone(),
+            });
+        }
+
+        for entry in target.result.unused_entries() {
+            findings.push(Finding {
+                file_path: entry.file_path.clone(),
+                diagnostic: diagnostics::create_unused_var_diagnostic(&entry.name, entry.line, entry.column),
+                var_name: entry.name.clone(),
+                schema_var: None,
+                profile: target.profile.clone(),
+            });
+        }
+
+        findings
+    }
+}
+
+fn push_unique(findings: &mut Vec<Finding>, seen: &mut HashSet<(String, u32, String)>, finding: Finding) {
+    let key = (
+        finding.file_path.clone(),
+        finding.diagnostic.range.start.line,
+        finding.diagnostic.message.clone(),
+    );
+    if seen.insert(key) {
+        findings.push(finding);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    const SCHEMA: &str = "variables:\n  DATABASE_URL:\n    type: url\n    required: true\n  PORT:\n    type: integer\n    default: 3000\n";
+
+    fn rules(report: &CheckReport) -> Vec<(String, String)> {
+        report
+            .findings()
+            .iter()
+            .map(|f| (f.rule_id().to_string(), f.var_name.clone()))
+            .collect()
+    }
+
+    fn config(json: serde_json::Value) -> Config {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn passes_a_complete_env_file() {
+        let dir = TempDir::new("check-complete");
+        dir.write("env.schema.yml", SCHEMA);
+        dir.write(".env", "DATABASE_URL=postgres://localhost/app\nPORT=8080\n");
+
+        let report = check_workspace(dir.path(), &Config::default(), &[]).unwrap();
+
+        assert!(!report.has_errors());
+        assert!(report.findings().is_empty());
+        assert_eq!(report.targets.len(), 1);
+        assert_eq!(report.schema_vars.len(), 2);
+    }
+
+    #[test]
+    fn reports_missing_invalid_and_unused_variables_with_locations() {
+        let dir = TempDir::new("check-findings");
+        dir.write("env.schema.yml", SCHEMA);
+        let env_path = dir.write(".env", "PORT=eighty\nLEGACY=1\n");
+
+        let report = check_workspace(dir.path(), &Config::default(), &[]).unwrap();
+
+        assert!(report.has_errors());
+        assert_eq!(
+            rules(&report),
+            [
+                ("missing-env-var".to_string(), "DATABASE_URL".to_string()),
+                ("invalid-env-value".to_string(), "PORT".to_string()),
+                ("unused-env-var".to_string(), "LEGACY".to_string()),
+            ]
+        );
+        let findings = report.findings();
+        assert!(findings.iter().all(|f| f.file_path == env_path.to_string_lossy()));
+        assert_eq!(findings[1].diagnostic.range.start.line, 0);
+        assert_eq!(findings[2].diagnostic.range.start.line, 1);
+    }
+
+    #[test]
+    fn checks_each_profile_separately() {
+        let dir = TempDir::new("check-profiles");
+        dir.write("env.schema.yml", SCHEMA);
+        dir.write(".env", "PORT=8080\n");
+        dir.write(".env.production", "DATABASE_URL=postgres://db/app\n");
+        let config = config(serde_json::json!({
+            "profiles": { "development": [".env"], "production": [".env", ".env.production"] }
+        }));
+
+        let report = check_workspace(dir.path(), &config, &[]).unwrap();
+        let profiles: Vec<_> = report.targets.iter().map(|t| t.profile.as_deref()).collect();
+        assert_eq!(profiles, [Some("development"), Some("production")]);
+        assert_eq!(report.findings().len(), 1);
+        assert_eq!(report.findings()[0].profile.as_deref(), Some("development"));
+
+        let production = check_workspace(dir.path(), &config, &["production".to_string()]).unwrap();
+        assert!(!production.has_errors());
+
+        let unknown = check_workspace(dir.path(), &config, &["staging".to_string()]).unwrap_err();
+        assert_eq!(unknown.to_string(), "Unknown profile 'staging'");
+    }
+
+    #[test]
+    fn checks_packages_against_their_own_schemas() {
+        let dir = TempDir::new("check-packages");
+        dir.write("env.schema.yml", SCHEMA);
+        dir.write(".env", "DATABASE_URL=postgres://db/app\n");
+        dir.write("apps/api/package.json", "{}");
+        dir.write("apps/api/env.schema.yml", "variables:\n  API_KEY:\n    type: string\n    required: true\n");
+        dir.write("apps/api/.env", "API_KEY=abc\n");
+        let config = config(serde_json::json!({ "envFiles": [".env", "apps/api/.env"] }));
+
+        let report = check_workspace(dir.path(), &config, &[]).unwrap();
+
+        assert_eq!(report.targets.len(), 2);
+        let api = report.targets.iter().find(|t| t.files[0].ends_with("apps/api/.env")).unwrap();
+        let api_vars: Vec<_> = api.schema_vars.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(api_vars, ["API_KEY"]);
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn checks_the_environment_against_declared_variables_only() {
+        let dir = TempDir::new("check-environment");
+        dir.write("env.schema.yml", SCHEMA);
+        let vars = [("PORT", "abc"), ("PATH", "/usr/bin"), ("DATABASE_URL", "")];
+        let entries = env_file::entries_from_vars(
+            vars.iter().map(|(name, value)| (name.to_string(), value.to_string())),
+            env_file::PROCESS_ENV_SOURCE,
+        );
+
+        let report = check_environment(dir.path(), &Config::default(), entries, env_file::PROCESS_ENV_SOURCE, None).unwrap();
+
+        assert_eq!(
+            rules(&report),
+            [
+                ("missing-env-var".to_string(), "DATABASE_URL".to_string()),
+                ("invalid-env-value".to_string(), "PORT".to_string()),
+            ]
+        );
+        assert_eq!(report.environment.as_deref(), Some(env_file::PROCESS_ENV_SOURCE));
+    }
+}
@@ -0,0 +1,389 @@
+use crate::check;
+use crate::codegen::{self, CodeTarget, SchemaFormat};
+use crate::config;
+use crate::env_file;
+use crate::example;
+use crate::infer;
+use crate::report::{self, ReportFormat};
+use crate::schema;
+use crate::types::SchemaSource;
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "\
+Usage: env-checker-lsp [COMMAND]
+
+Commands:
+  (none)              Run the language server over stdio
+  check [PATH]        Validate the env files of the workspace at PATH (default: .)
+  generate example [PATH]
+                      Write a .env.example for the workspace at PATH from its schemas
+  generate schema [PATH]
+                      Write a schema inferred from the workspace's existing .env
+  generate code [PATH] --format LANG
+                      Write typed accessors for the workspace's YAML schema
+  help                Print this help
+
+Options for `check`:
+  -p, --profile NAME  Only check the given profile (can be repeated); with
+                      --from-env or --from-env-json, the profile the
+                      environment belongs to
+  -f, --format FMT    Output format: human (default), json, sarif, junit
+  -o, --output FILE   Write the report to FILE instead of stdout
+  --from-env          Validate the environment of this process instead of env files
+  --from-env-json FILE
+                      Validate a JSON dump of an environment instead of env files
+
+Options for `generate`:
+  -o, --output FILE   Write to FILE instead of the default location
+  --check             Do not write anything; exit with 1 if the file is out of date
+  -f, --format FMT    Schema format for `generate schema`: yaml (default), zod, pydantic
+                      Language for `generate code`: zod, pydantic, dts, rust
+  --from FILE         Env file to infer the schema from (default: PATH/.env)
+  --force             Overwrite an existing schema file
+  --schema FILE       YAML schema to generate code from (default: the one in PATH)
+";
+
+#[derive(Debug)]
+pub enum Command {
+    Serve,
+    Check(CheckArgs),
+    Generate(GenerateArgs),
+    Help,
+}
+
+#[derive(Debug, Default)]
+pub struct CheckArgs {
+    pub workspace_root: Option<PathBuf>,
+    pub profiles: Vec<String>,
+    pub format: ReportFormat,
+    pub output: Option<PathBuf>,
+    pub env_source: Option<EnvSource>,
+}
+
+/// What `generate` produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerateTarget {
+    Example,
+    Schema(SchemaFormat),
+    Code(Option<CodeTarget>),
+}
+
+#[derive(Debug)]
+pub struct GenerateArgs {
+    pub target: GenerateTarget,
+    pub workspace_root: Option<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub check: bool,
+    pub from: Option<PathBuf>,
+    pub force: bool,
+    pub schema: Option<PathBuf>,
+}
+
+/// Where `check` reads variables from when not using env files.
+#[derive(Debug)]
+pub enum EnvSource {
+    Process,
+    Json(PathBuf),
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+
+    let command = match args.next() {
+        None => return Ok(Command::Serve),
+        Some(arg) => arg,
+    };
+
+    match command.as_str() {
+        "--stdio" | "serve" => Ok(Command::Serve),
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        "check" => parse_check_args(args).map(Command::Check),
+        "generate" => parse_generate_args(args).map(Command::Generate),
+        other => Err(format!("Unknown command '{}'", other)),
+    }
+}
+
+fn parse_check_args(mut args: impl Iterator<Item = String>) -> Result<CheckArgs, String> {
+    let mut check_args = CheckArgs::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" | "--profile" => {
+                let profile = args.next().ok_or("Missing value for --profile")?;
+                check_args.profiles.push(profile);
+            }
+            "-f" | "--format" => {
+                let format = args.next().ok_or("Missing value for --format")?;
+                check_args.format = ReportFormat::parse(&format)
+                    .ok_or_else(|| format!("Unknown format '{}'", format))?;
+            }
+            "-o" | "--output" => {
+                let output = args.next().ok_or("Missing value for --output")?;
+                check_args.output = Some(PathBuf::from(output));
+            }
+            "--from-env" => check_args.env_source = Some(EnvSource::Process),
+            "--from-env-json" => {
+                let path = args.next().ok_or("Missing value for --from-env-json")?;
+                check_args.env_source = Some(EnvSource::Json(PathBuf::from(path)));
+            }
+            flag if flag.starts_with('-') => return Err(format!("Unknown option '{}'", flag)),
+            path => {
+                if check_args.workspace_root.is_some() {
+                    return Err(format!("Unexpected argument '{}'", path));
+                }
+                check_args.workspace_root = Some(PathBuf::from(path));
+            }
+        }
+    }
+
+    if check_args.env_source.is_some() && check_args.profiles.len() > 1 {
+        return Err("--from-env and --from-env-json take at most one --profile".to_string());
+    }
+
+    Ok(check_args)
+}
+
+fn parse_generate_args(mut args: impl Iterator<Item = String>) -> Result<GenerateArgs, String> {
+    let target = match args.next().as_deref() {
+        Some("example") => GenerateTarget::Example,
+        Some("schema") => GenerateTarget::Schema(SchemaFormat::Yaml),
+        Some("code") => GenerateTarget::Code(None),
+        Some(other) => return Err(format!("Unknown generate target '{}'", other)),
+        None => return Err("Missing generate target, expected 'example', 'schema' or 'code'".to_string()),
+    };
+
+    let mut generate_args = GenerateArgs {
+        target,
+        workspace_root: None,
+        output: None,
+        check: false,
+        from: None,
+        force: false,
+        schema: None,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => {
+                let output = args.next().ok_or("Missing value for --output")?;
+                generate_args.output = Some(PathBuf::from(output));
+            }
+            "--check" => generate_args.check = true,
+            "-f" | "--format" if matches!(generate_args.target, GenerateTarget::Schema(_)) => {
+                let format = args.next().ok_or("Missing value for --format")?;
+                let format = SchemaFormat::parse(&format).ok_or_else(|| format!("Unknown schema format '{}'", format))?;
+                generate_args.target = GenerateTarget::Schema(format);
+            }
+            "-f" | "--format" if matches!(generate_args.target, GenerateTarget::Code(_)) => {
+                let format = args.next().ok_or("Missing value for --format")?;
+                let target = CodeTarget::parse(&format).ok_or_else(|| format!("Unknown code format '{}'", format))?;
+                generate_args.target = GenerateTarget::Code(Some(target));
+            }
+            "--schema" if matches!(generate_args.target, GenerateTarget::Code(_)) => {
+                let schema = args.next().ok_or("Missing value for --schema")?;
+                generate_args.schema = Some(PathBuf::from(schema));
+            }
+            "--from" if matches!(generate_args.target, GenerateTarget::Schema(_)) => {
+                let from = args.next().ok_or("Missing value for --from")?;
+                generate_args.from = Some(PathBuf::from(from));
+            }
+            "--force" => generate_args.force = true,
+            flag if flag.starts_with('-') => return Err(format!("Unknown option '{}'", flag)),
+            path => {
+                if generate_args.workspace_root.is_some() {
+                    return Err(format!("Unexpected argument '{}'", path));
+                }
+                generate_args.workspace_root = Some(PathBuf::from(path));
+            }
+        }
+    }
+
+    if generate_args.target == GenerateTarget::Code(None) {
+        return Err("Missing --format for generate code, expected zod, pydantic, dts or rust".to_string());
+    }
+
+    Ok(generate_args)
+}
+
+/// Runs `check` and returns the process exit code: 0 when the workspace is
+/// valid, 1 when errors were found and 2 when the check could not run.
+pub fn run_check(args: CheckArgs) -> i32 {
+    let root = args.workspace_root.unwrap_or_else(|| PathBuf::from("."));
+    let root = match root.canonicalize() {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("error: cannot open workspace {}: {}", root.display(), e);
+            return 2;
+        }
+    };
+
+    let config = match config::load_config(&root) {
+        Ok(config) => config.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            return 2;
+        }
+    };
+
+    let profile = args.profiles.first().map(String::as_str);
+    let report = match &args.env_source {
+        None => check::check_workspace(&root, &config, &args.profiles),
+        Some(EnvSource::Process) => {
+            let entries = env_file::entries_from_process_env();
+            check::check_environment(&root, &config, entries, env_file::PROCESS_ENV_SOURCE, profile)
+        }
+        Some(EnvSource::Json(path)) => env_file::parse_env_json(path).and_then(|entries| {
+            check::check_environment(&root, &config, entries, &path.to_string_lossy(), profile)
+        }),
+    };
+
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            return 2;
+        }
+    };
+
+    let rendered = report::render(&report, args.format);
+
+    match &args.output {
+        Some(path) => {
+            if let Err(e) = fs::write(path, rendered) {
+                eprintln!("error: cannot write {}: {}", path.display(), e);
+                return 2;
+            }
+        }
+        None => print!("{}", rendered),
+    }
+
+    if report.has_errors() {
+        1
+    } else {
+        0
+    }
+}
+
+/// Runs `generate` and returns the process exit code. With `--check`, 1 means
+/// the file is missing or out of date; otherwise the file is written and 0 is
+/// returned. 2 means the command could not run.
+pub fn run_generate(args: GenerateArgs) -> i32 {
+    let root = args.workspace_root.clone().unwrap_or_else(|| PathBuf::from("."));
+    let root = match root.canonicalize() {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("error: cannot open workspace {}: {}", root.display(), e);
+            return 2;
+        }
+    };
+
+    let generated = match args.target {
+        GenerateTarget::Example => generate_example(&root, &args),
+        GenerateTarget::Schema(format) => generate_schema(&root, &args, format),
+        GenerateTarget::Code(Some(target)) => generate_code(&root, &args, target),
+        GenerateTarget::Code(None) => unreachable!("the code target is checked while parsing"),
+    };
+
+    match generated {
+        Ok((output, existing, content)) => write_generated(&output, existing.as_deref(), &content, args.check),
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            2
+        }
+    }
+}
+
+type Generated = (PathBuf, Option<String>, String);
+
+fn generate_example(root: &Path, args: &GenerateArgs) -> Result<Generated> {
+    let config = config::load_config(root)?.unwrap_or_default();
+    let schema_sources = config::discover_schemas(root, &config)?;
+    let schema_vars = schema::load_schemas(&schema_sources);
+
+    // Without variables, updating would strip every entry from an existing file.
+    if schema_vars.is_empty() {
+        bail!("No schema variables found in {}, refusing to generate .env.example", root.display());
+    }
+
+    let output = args.output.clone().unwrap_or_else(|| root.join(".env.example"));
+    let existing = fs::read_to_string(&output).ok();
+    let content = example::update_example_content(existing.as_deref().unwrap_or(""), &schema_vars, &config.groups);
+
+    Ok((output, existing, content))
+}
+
+fn generate_schema(root: &Path, args: &GenerateArgs, format: SchemaFormat) -> Result<Generated> {
+    let from = args.from.clone().unwrap_or_else(|| root.join(".env"));
+    let env_content = fs::read_to_string(&from)
+        .context(format!("Failed to read env file: {}", from.display()))?;
+    let vars = infer::infer_variables(&env_content);
+
+    let output = args.output.clone().unwrap_or_else(|| root.join(format.default_file_name()));
+    let existing = fs::read_to_string(&output).ok();
+    let content = codegen::render_schema(&vars, format);
+
+    if existing.as_deref().is_some_and(|e| e != content) && !args.check && !args.force {
+        bail!("{} already exists, pass --force to overwrite it", output.display());
+    }
+
+    Ok((output, existing, content))
+}
+
+fn generate_code(root: &Path, args: &GenerateArgs, target: CodeTarget) -> Result<Generated> {
+    let schema_path = match &args.schema {
+        Some(path) => path.clone(),
+        None => {
+            let config = config::load_config(root)?.unwrap_or_default();
+            let yaml_sources: Vec<_> = config::discover_schemas(root, &config)?
+                .into_iter()
+                .filter(|source| matches!(source, SchemaSource::Yaml { .. }))
+                .collect();
+
+            match yaml_sources.as_slice() {
+                [] => bail!("No YAML schema found in {}, pass one with --schema", root.display()),
+                [source] => PathBuf::from(source.file_path()),
+                _ => bail!("Several YAML schemas found in {}, pick one with --schema", root.display()),
+            }
+        }
+    };
+
+    let source = SchemaSource::Yaml {
+        file_path: schema_path.to_string_lossy().to_string(),
+    };
+    let parsed = schema::parse_schema(&source)?
+        .with_context(|| format!("{} does not declare any variables", schema_path.display()))?;
+
+    let output = args.output.clone().unwrap_or_else(|| root.join(target.default_file_name()));
+    let existing = fs::read_to_string(&output).ok();
+    let content = codegen::render_code(&parsed, target);
+
+    Ok((output, existing, content))
+}
+
+fn write_generated(output: &Path, existing: Option<&str>, content: &str, check: bool) -> i32 {
+    if existing == Some(content) {
+        println!("{} is up to date", output.display());
+        return 0;
+    }
+
+    if check {
+        match existing {
+            Some(_) => eprintln!("{} is out of date, run `env-checker-lsp generate` without --check to update it", output.display()),
+            None => eprintln!("{} does not exist, run `env-checker-lsp generate` without --check to create it", output.display()),
+        }
+        return 1;
+    }
+
+    if let Err(e) = fs::write(output, content) {
+        eprintln!("error: cannot write {}: {}", output.display(), e);
+        return 2;
+    }
+
+    match existing {
+        Some(_) => println!("Updated {}", output.display()),
+        None => println!("Created {}", output.display()),
+    }
+    0
+}
@@ -1,20 +1,24 @@
-use crate::types::EnvVar;
+use crate::codegen::{render_schema, SchemaFormat};
+use crate::example::{format_group_header, format_var_entry, generate_example_content, update_example_content};
+use crate::types::{EnvFileEntry, EnvVar, Groups};
 use crate::validation::group_variables;
 use tower_lsp::lsp_types::{
-    CodeAction, CodeActionKind, CodeActionOrCommand, Position, Range, 
-    TextEdit, WorkspaceEdit, Url,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CreateFile, CreateFileOptions, Diagnostic,
+    DocumentChangeOperation, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier,
+    Position, Range, ResourceOp, TextDocumentEdit, TextEdit, WorkspaceEdit, Url,
 };
 use std::collections::HashMap;
 
 pub fn create_append_missing_action(
     missing_vars: &[EnvVar],
+    groups: &Groups,
     file_uri: &str,
     last_line: usize,
     line_content: Option<String>,
 ) -> CodeActionOrCommand {
     let mut edits = Vec::new();
 
-    let content = generate_env_content(missing_vars);
+    let content = generate_env_content(missing_vars, groups);
 
     let position = if let Some(line) = line_content {
         if line.trim().is_empty() {
@@ -61,16 +65,15 @@ pub fn create_append_missing_action(
     .into()
 }
 
-pub fn create_generate_example_action(
-    all_vars: &[EnvVar],
+pub fn create_rename_deprecated_action(
+    entry: &EnvFileEntry,
+    replacement: &str,
     file_uri: &str,
+    diagnostic: Option<Diagnostic>,
 ) -> CodeActionOrCommand {
-    let content = generate_example_content(all_vars);
-
-    let text_edit = TextEdit::new(
-        Range::new(Position::new(0, 0), Position::new(u32::MAX, u32::MAX)),
-        content,
-    );
+    let start = Position::new(entry.line as u32, entry.column as u32);
+    let end = Position::new(entry.line as u32, (entry.column + entry.name.len()) as u32);
+    let text_edit = TextEdit::new(Range::new(start, end), replacement.to_string());
 
     let uri = Url::parse(file_uri).unwrap_or_else(|_| Url::from_file_path(file_uri).unwrap());
     let mut workspace_edits = HashMap::new();
@@ -83,10 +86,28 @@ pub fn create_generate_example_action(
     };
 
     CodeAction {
-        title: "Generate .env.example file".to_string(),
+        title: format!("Rename '{}' to '{}'", entry.name, replacement),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: diagnostic.map(|d| vec![d]),
+        edit: Some(workspace_edit),
+        command: None,
+        is_preferred: Some(true),
+        disabled: None,
+        data: None,
+    }
+    .into()
+}
+
+/// Creates `.env.example` with all schema variables.
+pub fn create_create_example_action(all_vars: &[EnvVar], groups: &Groups, example_uri: &Url) -> CodeActionOrCommand {
+    let mut content = generate_example_content(all_vars, groups);
+    content.push('\n');
+
+    CodeAction {
+        title: "Create .env.example".to_string(),
         kind: Some(CodeActionKind::SOURCE),
         diagnostics: None,
-        edit: Some(workspace_edit),
+        edit: Some(create_file_edit(example_uri, content)),
         command: None,
         is_preferred: Some(false),
         disabled: None,
@@ -95,27 +116,81 @@ pub fn create_generate_example_action(
     .into()
 }
 
-pub fn create_create_example_action(
+/// Brings an existing `.env.example` in line with the schemas, keeping
+/// hand-written comments and ordering. Returns `None` when it is up to date,
+/// or when there are no schema variables, which would empty the file.
+pub fn create_update_example_action(
     all_vars: &[EnvVar],
-    workspace_uri: &str,
-) -> CodeActionOrCommand {
-    let example_uri = format!("{}/.env.example", workspace_uri.trim_end_matches('/'));
-    let uri = Url::parse(&example_uri).unwrap_or_else(|_| Url::from_file_path(&example_uri).unwrap());
+    groups: &Groups,
+    example_uri: &Url,
+    existing: &str,
+) -> Option<CodeActionOrCommand> {
+    if all_vars.is_empty() {
+        return None;
+    }
 
-    let mut workspace_edits = HashMap::new();
-    workspace_edits.insert(uri, vec![]);
+    let content = update_example_content(existing, all_vars, groups);
+    if content == existing {
+        return None;
+    }
+
+    let line_count = existing.split('\n').count();
+    let last_line = existing.rsplit('\n').next().unwrap_or("");
+    let end = Position::new(
+        (line_count - 1) as u32,
+        last_line.encode_utf16().count() as u32,
+    );
+
+    let edit = TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier {
+            uri: example_uri.clone(),
+            version: None,
+        },
+        edits: vec![OneOf::Left(TextEdit::new(
+            Range::new(Position::new(0, 0), end),
+            content,
+        ))],
+    };
 
     let workspace_edit = WorkspaceEdit {
-        changes: Some(workspace_edits),
-        document_changes: None,
+        changes: None,
+        document_changes: Some(DocumentChanges::Edits(vec![edit])),
         change_annotations: None,
     };
 
+    Some(
+        CodeAction {
+            title: "Update .env.example".to_string(),
+            kind: Some(CodeActionKind::SOURCE),
+            diagnostics: None,
+            edit: Some(workspace_edit),
+            command: None,
+            is_preferred: Some(false),
+            disabled: None,
+            data: None,
+        }
+        .into(),
+    )
+}
+
+/// Creates a schema file from the variables inferred from an env file, for
+/// projects that do not have a schema yet.
+pub fn create_generate_schema_action(
+    inferred_vars: &[EnvVar],
+    format: SchemaFormat,
+    schema_uri: &Url,
+    env_file_name: &str,
+) -> CodeActionOrCommand {
+    let file_name = schema_uri
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or(format.default_file_name());
+
     CodeAction {
-        title: "Create .env.example file".to_string(),
+        title: format!("Generate {} ({}) from {}", format.label(), file_name, env_file_name),
         kind: Some(CodeActionKind::SOURCE),
         diagnostics: None,
-        edit: Some(workspace_edit),
+        edit: Some(create_file_edit(schema_uri, render_schema(inferred_vars, format))),
         command: None,
         is_preferred: Some(false),
         disabled: None,
@@ -124,55 +199,53 @@ pub fn create_create_example_action(
     .into()
 }
 
-fn generate_env_content(vars: &[EnvVar]) -> String {
-    let grouped = group_variables(vars);
-    let mut content = String::new();
-
-    for (group, group_vars) in grouped {
-        content.push_str(&format!("\n# {}\n", group));
-        
-        for var in &group_vars {
-            content.push_str(&format_var_entry(var, false));
-        }
-    }
-
-    if !content.is_empty() && content.starts_with('\n') {
-        content.remove(0);
+/// A `CreateFile` resource operation followed by an edit filling the new
+/// file. Clients need `workspace.workspaceEdit.resourceOperations` for it.
+fn create_file_edit(uri: &Url, content: String) -> WorkspaceEdit {
+    let operations = vec![
+        DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+            uri: uri.clone(),
+            options: Some(CreateFileOptions {
+                overwrite: Some(false),
+                ignore_if_exists: Some(true),
+            }),
+            annotation_id: None,
+        })),
+        DocumentChangeOperation::Edit(TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                uri: uri.clone(),
+                version: None,
+            },
+            edits: vec![OneOf::Left(TextEdit::new(
+                Range::new(Position::new(0, 0), Position::new(0, 0)),
+                content,
+            ))],
+        }),
+    ];
+
+    WorkspaceEdit {
+        changes: None,
+        document_changes: Some(DocumentChanges::Operations(operations)),
+        change_annotations: None,
     }
-
-    content
 }
 
-fn generate_example_content(vars: &[EnvVar]) -> String {
-    let grouped = group_variables(vars);
+fn generate_env_content(vars: &[EnvVar], groups: &Groups) -> String {
+    let grouped = group_variables(vars, groups);
     let mut content = String::new();
 
-    for (group, group_vars) in grouped {
-        content.push_str(&format!("# {}\n", group));
-        
-        for var in &group_vars {
-            content.push_str(&format_var_entry(var, true));
-        }
-
+    for group in grouped {
         content.push('\n');
-    }
-
-    content.trim_end_matches('\n').to_string()
-}
+        content.push_str(&format_group_header(&group));
 
-fn format_var_entry(var: &EnvVar, _is_example: bool) -> String {
-    let mut result = String::new();
-
-    if let Some(desc) = &var.description {
-        result.push_str(&format!("# {}\n", desc));
+        for var in &group.vars {
+            content.push_str(&format_var_entry(var, false));
+        }
     }
 
-    if var.optional {
-        result.push('#');
+    if !content.is_empty() && content.starts_with('\n') {
+        content.remove(0);
     }
 
-    let default_value = var.default.as_deref().unwrap_or("");
-    result.push_str(&format!("{}={}\n", var.name, default_value));
-
-    result
+    content
 }
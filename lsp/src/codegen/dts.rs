@@ -0,0 +1,59 @@
+use crate::codegen::quote;
+use crate::types::EnvVar;
+
+/// Renders a declaration file that types `process.env` for Node.js.
+///
+/// Values in `process.env` are always strings, so only enums narrow the
+/// type. Variables with a default or marked optional may be unset.
+pub fn render_process_env_types(vars: &[EnvVar]) -> String {
+    let mut content = String::from("declare global {\n    namespace NodeJS {\n        interface ProcessEnv {\n");
+
+    for var in vars {
+        let doc = doc_comment(var);
+        if !doc.is_empty() {
+            content.push_str(&doc);
+        }
+
+        let optional = if var.optional || var.default.is_some() { "?" } else { "" };
+        let value_type = if var.allowed_values.is_empty() {
+            "string".to_string()
+        } else {
+            let values: Vec<String> = var.allowed_values.iter().map(|v| quote(v)).collect();
+            values.join(" | ")
+        };
+
+        content.push_str(&format!("            {}{}: {};\n", var.name, optional, value_type));
+    }
+
+    content.push_str("        }\n    }\n}\n\nexport {};\n");
+    content
+}
+
+fn doc_comment(var: &EnvVar) -> String {
+    let mut lines = Vec::new();
+
+    if let Some(description) = &var.description {
+        lines.push(description.clone());
+    }
+    if let Some(default) = &var.default {
+        lines.push(format!("@default {}", quote(default)));
+    }
+    match var.deprecated.as_deref() {
+        Some("") => lines.push("@deprecated".to_string()),
+        Some(message) => lines.push(format!("@deprecated {}", message)),
+        None => {}
+    }
+
+    match lines.as_slice() {
+        [] => String::new(),
+        [line] => format!("            /** {} */\n", line.replace("*/", "*\\/")),
+        _ => {
+            let mut doc = String::from("            /**\n");
+            for line in &lines {
+                doc.push_str(&format!("             * {}\n", line.replace("*/", "*\\/")));
+            }
+            doc.push_str("             */\n");
+            doc
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use crate::types::{EnvType, EnvVar, ParsedSchema};
+use std::path::Path;
+
+pub mod dts;
+pub mod pydantic;
+pub mod rust;
+pub mod yaml;
+pub mod zod;
+
+/// A schema format that variables can be written out as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaFormat {
+    Zod,
+    Pydantic,
+    Yaml,
+}
+
+impl SchemaFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "zod" | "ts" | "typescript" => Some(Self::Zod),
+            "pydantic" | "py" | "python" => Some(Self::Pydantic),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// The file a schema of this format is written to when no output is
+    /// given. Discovery recognizes generated schemas by their content, so
+    /// they are found under this name or any other.
+    pub fn default_file_name(self) -> &'static str {
+        match self {
+            Self::Zod => "env.ts",
+            Self::Pydantic => "settings.py",
+            Self::Yaml => "env.schema.yml",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Zod => "Zod schema",
+            Self::Pydantic => "Pydantic settings",
+            Self::Yaml => "YAML schema",
+        }
+    }
+}
+
+pub fn render_schema(vars: &[EnvVar], format: SchemaFormat) -> String {
+    match format {
+        SchemaFormat::Zod => zod::render_zod_schema(vars),
+        SchemaFormat::Pydantic => pydantic::render_pydantic_settings(vars),
+        SchemaFormat::Yaml => yaml::render_yaml_schema(vars),
+    }
+}
+
+/// A language that typed accessors can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeTarget {
+    Zod,
+    Pydantic,
+    ProcessEnv,
+    Rust,
+}
+
+impl CodeTarget {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "zod" | "ts" | "typescript" => Some(Self::Zod),
+            "pydantic" | "py" | "python" => Some(Self::Pydantic),
+            "dts" | "d.ts" | "process-env" => Some(Self::ProcessEnv),
+            "rust" | "rs" => Some(Self::Rust),
+            _ => None,
+        }
+    }
+
+    /// The file generated code is written to when no output is given.
+    pub fn default_file_name(self) -> &'static str {
+        match self {
+            Self::Zod => "env.generated.ts",
+            Self::Pydantic => "env_settings.py",
+            Self::ProcessEnv => "env.d.ts",
+            Self::Rust => "env.rs",
+        }
+    }
+
+    fn comment_prefix(self) -> &'static str {
+        match self {
+            Self::Pydantic => "#",
+            Self::Zod | Self::ProcessEnv | Self::Rust => "//",
+        }
+    }
+}
+
+/// Generates typed accessor code from a schema, so a YAML schema can be the
+/// single definition of the environment in repositories with several
+/// languages.
+pub fn render_code(schema: &ParsedSchema, target: CodeTarget) -> String {
+    let vars = &schema.variables;
+    let source_name = Path::new(schema.source.file_path())
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut content = format!(
+        "{} Generated by env-checker from {}. Do not edit.\n\n",
+        target.comment_prefix(),
+        source_name
+    );
+
+    match target {
+        CodeTarget::Zod => content.push_str(&zod::render_zod_schema(vars)),
+        CodeTarget::Pydantic => {
+            content.push_str(&pydantic::render_pydantic_settings(vars));
+            content.push_str("\n\nsettings = Settings()\n");
+        }
+        CodeTarget::ProcessEnv => content.push_str(&dts::render_process_env_types(vars)),
+        CodeTarget::Rust => content.push_str(&rust::render_rust_struct(vars)),
+    }
+
+    content
+}
+
+/// A default or example value, typed according to the variable's schema type
+/// so generators can emit it as a literal of the target language.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal<'a> {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(&'a str),
+}
+
+pub fn literal<'a>(var_type: &EnvType, value: &'a str) -> Literal<'a> {
+    match var_type {
+        EnvType::Boolean => match value.to_lowercase().as_str() {
+            "true" | "1" | "yes" => Literal::Bool(true),
+            "false" | "0" | "no" => Literal::Bool(false),
+            _ => Literal::Str(value),
+        },
+        EnvType::Integer => value.parse().map_or(Literal::Str(value), Literal::Int),
+        EnvType::Number => value.parse().map_or(Literal::Str(value), Literal::Float),
+        EnvType::String | EnvType::Url => Literal::Str(value),
+    }
+}
+
+/// Quotes a string with double quotes and JSON escapes, which are valid
+/// string literals in TypeScript, Python and YAML alike.
+pub fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+/// Formats a number bound so whole numbers are written without a fraction.
+pub fn format_bound(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config;
+    use crate::test_support::TempDir;
+    use crate::types::{Config, Rules, SchemaSource};
+
+    fn sample_vars() -> Vec<EnvVar> {
+        vec![
+            EnvVar { name: "DATABASE_URL".to_string(), var_type: EnvType::Url, ..Default::default() },
+            EnvVar {
+                name: "PORT".to_string(),
+                var_type: EnvType::Integer,
+                default: Some("3000".to_string()),
+                optional: true,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn var(name: &str, var_type: EnvType) -> EnvVar {
+        EnvVar { name: name.to_string(), var_type, ..Default::default() }
+    }
+
+    #[test]
+    fn generated_schemas_parse_back_to_the_same_variables() {
+        let vars = vec![
+            EnvVar { description: Some("Primary database".to_string()), group: Some("Database".to_string()), ..var("DATABASE_URL", EnvType::Url) },
+            EnvVar { default: Some("3000".to_string()), optional: true, ..var("PORT", EnvType::Integer) },
+            EnvVar { default: Some("0.5".to_string()), optional: true, ..var("SAMPLE_RATE", EnvType::Number) },
+            EnvVar { default: Some("false".to_string()), optional: true, ..var("DEBUG", EnvType::Boolean) },
+            EnvVar { optional: true, ..var("API_KEY", EnvType::String) },
+        ];
+
+        for format in [SchemaFormat::Zod, SchemaFormat::Pydantic, SchemaFormat::Yaml] {
+            let dir = TempDir::new(&format!("codegen-roundtrip-{}", format.label()));
+            let discovered = {
+                dir.write(format.default_file_name(), &render_schema(&vars, format));
+                config::discover_schemas(dir.path(), &Config::default()).unwrap()
+            };
+            let parsed = crate::schema::parse_schema(&discovered[0]).unwrap().unwrap();
+
+            let names: Vec<&str> = parsed.variables.iter().map(|v| v.name.as_str()).collect();
+            assert_eq!(names, ["DATABASE_URL", "PORT", "SAMPLE_RATE", "DEBUG", "API_KEY"], "{}", format.label());
+            for (expected, actual) in vars.iter().zip(&parsed.variables) {
+                let context = format!("{} in the {}", expected.name, format.label());
+                assert_eq!(actual.var_type, expected.var_type, "type of {}", context);
+                assert_eq!(actual.optional, expected.optional, "optional of {}", context);
+                assert_eq!(actual.default, expected.default, "default of {}", context);
+                assert_eq!(actual.description, expected.description, "description of {}", context);
+                // Pydantic settings carry groups only as comments.
+                if format != SchemaFormat::Pydantic {
+                    assert_eq!(actual.group, expected.group, "group of {}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_schemas_are_discovered() {
+        for format in [SchemaFormat::Zod, SchemaFormat::Pydantic, SchemaFormat::Yaml] {
+            let dir = TempDir::new(&format!("codegen-discover-{}", format.label()));
+            let path = dir.write(format.default_file_name(), &render_schema(&sample_vars(), format));
+
+            let discovered = config::discover_schemas(dir.path(), &Config::default()).unwrap();
+            let file_path = path.to_string_lossy().to_string();
+            let expected = match format {
+                SchemaFormat::Zod => SchemaSource::Zod { file_path },
+                SchemaFormat::Pydantic => SchemaSource::Pydantic { file_path },
+                SchemaFormat::Yaml => SchemaSource::Yaml { file_path },
+            };
+            assert_eq!(discovered, vec![expected], "{}", format.label());
+        }
+    }
+
+    fn code_schema() -> ParsedSchema {
+        ParsedSchema {
+            source: SchemaSource::Yaml { file_path: "/repo/env.schema.yml".to_string() },
+            variables: vec![
+                EnvVar {
+                    description: Some("Primary database".to_string()),
+                    group: Some("Database".to_string()),
+                    ..var("DATABASE_URL", EnvType::Url)
+                },
+                EnvVar {
+                    default: Some("3000".to_string()),
+                    optional: true,
+                    min: Some(1.0),
+                    max: Some(65535.0),
+                    ..var("PORT", EnvType::Integer)
+                },
+                EnvVar {
+                    default: Some("info".to_string()),
+                    optional: true,
+                    allowed_values: vec!["debug".to_string(), "info".to_string()],
+                    ..var("LOG_LEVEL", EnvType::String)
+                },
+                EnvVar { optional: true, ..var("DEBUG", EnvType::Boolean) },
+                EnvVar { secret: true, ..var("API_KEY", EnvType::String) },
+            ],
+            rules: Rules::default(),
+        }
+    }
+
+    fn assert_lines(content: &str, expected: &[&str]) {
+        for line in expected {
+            assert!(content.lines().any(|l| l.trim() == *line), "missing `{}` in:\n{}", line, content);
+        }
+    }
+
+    #[test]
+    fn renders_a_zod_module() {
+        let content = render_code(&code_schema(), CodeTarget::Zod);
+
+        assert!(content.starts_with("// Generated by env-checker from env.schema.yml. Do not edit.\n"));
+        assert_lines(
+            &content,
+            &[
+                "DATABASE_URL: z.url()",
+                ".describe(\"Primary database\")",
+                ".register(envRegistry, { group: \"Database\" }),",
+                "PORT: z.coerce.number().int().min(1).max(65535).default(3000),",
+                "LOG_LEVEL: z.enum([\"debug\", \"info\"]).default(\"info\"),",
+                "DEBUG: z.stringbool().optional(),",
+                "API_KEY: z.string(),",
+                "export const env = envSchema.parse(process.env);",
+            ],
+        );
+    }
+
+    #[test]
+    fn renders_pydantic_settings() {
+        let content = render_code(&code_schema(), CodeTarget::Pydantic);
+
+        assert!(content.starts_with("# Generated by env-checker from env.schema.yml. Do not edit.\n"));
+        assert_lines(
+            &content,
+            &[
+                "from pydantic import AnyUrl, Field, SecretStr",
+                "# Database",
+                "database_url: AnyUrl = Field(description=\"Primary database\")",
+                "port: int = Field(default=3000, ge=1, le=65535)",
+                "log_level: Literal[\"debug\", \"info\"] = Field(default=\"info\")",
+                "debug: bool | None = None",
+                "api_key: SecretStr",
+                "settings = Settings()",
+            ],
+        );
+    }
+
+    #[test]
+    fn renders_process_env_types() {
+        let content = render_code(&code_schema(), CodeTarget::ProcessEnv);
+
+        assert_lines(
+            &content,
+            &[
+                "interface ProcessEnv {",
+                "/** Primary database */",
+                "DATABASE_URL: string;",
+                "PORT?: string;",
+                "LOG_LEVEL?: \"debug\" | \"info\";",
+                "DEBUG?: string;",
+                "API_KEY: string;",
+                "export {};",
+            ],
+        );
+    }
+
+    #[test]
+    fn renders_a_rust_struct_that_compiles() {
+        let content = render_code(&code_schema(), CodeTarget::Rust);
+
+        assert_lines(
+            &content,
+            &[
+                "pub database_url: String,",
+                "pub port: i64,",
+                "pub debug: Option<bool>,",
+                "database_url: required(\"DATABASE_URL\")?,",
+                "port: parse(\"PORT\", get(\"PORT\").unwrap_or_else(|| \"3000\".to_string()))?,",
+            ],
+        );
+
+        let dir = TempDir::new("codegen-rust");
+        let path = dir.write("env.rs", &content);
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let output = std::process::Command::new(rustc)
+            .args(["--edition", "2021", "--crate-type", "lib", "--emit", "metadata", "-D", "warnings", "--out-dir"])
+            .arg(dir.path())
+            .arg(&path)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    }
+}
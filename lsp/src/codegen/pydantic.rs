@@ -0,0 +1,129 @@
+use crate::codegen::{format_bound, literal, quote, Literal};
+use crate::types::{EnvType, EnvVar};
+
+/// Renders a Python module with a `pydantic_settings.BaseSettings` class.
+///
+/// Field names are lower case; pydantic-settings matches them against the
+/// environment case-insensitively. Groups are written as comments.
+pub fn render_pydantic_settings(vars: &[EnvVar]) -> String {
+    let mut pydantic_imports = vec!["Field"];
+    if vars.iter().any(|v| v.var_type == EnvType::Url && v.allowed_values.is_empty()) {
+        pydantic_imports.insert(0, "AnyUrl");
+    }
+    if vars.iter().any(is_secret_str) {
+        pydantic_imports.push("SecretStr");
+    }
+
+    let mut content = String::new();
+    if vars.iter().any(|v| !v.allowed_values.is_empty()) {
+        content.push_str("from typing import Literal\n\n");
+    }
+    content.push_str(&format!("from pydantic import {}\n", pydantic_imports.join(", ")));
+    content.push_str("from pydantic_settings import BaseSettings\n\n\n");
+    content.push_str("class Settings(BaseSettings):\n");
+
+    if vars.is_empty() {
+        content.push_str("    pass\n");
+    }
+
+    let mut current_group = None;
+    for (i, var) in vars.iter().enumerate() {
+        if var.group.is_some() && var.group != current_group {
+            if i > 0 {
+                content.push('\n');
+            }
+            content.push_str(&format!("    # {}\n", var.group.as_deref().unwrap_or_default()));
+        }
+        current_group = var.group.clone();
+
+        content.push_str(&format!("    {}\n", field(var)));
+    }
+
+    content
+}
+
+fn field(var: &EnvVar) -> String {
+    let mut type_hint = type_hint(var);
+    let is_str = type_hint == "str";
+    if var.optional && var.default.is_none() {
+        type_hint.push_str(" | None");
+    }
+
+    let mut args = Vec::new();
+    match &var.default {
+        Some(default) => args.push(format!("default={}", default_literal(var, default))),
+        None if var.optional => args.push("None".to_string()),
+        None => {}
+    }
+
+    if let Some(description) = &var.description {
+        args.push(format!("description={}", quote(description)));
+    }
+
+    // Pydantic rejects length and pattern constraints on anything but `str`.
+    let is_number = matches!(var.var_type, EnvType::Integer | EnvType::Number);
+    let bound_keys = match (is_number, is_str) {
+        _ if !var.allowed_values.is_empty() => None,
+        (true, _) => Some(("ge", "le")),
+        (_, true) => Some(("min_length", "max_length")),
+        _ => None,
+    };
+    if let Some((min_key, max_key)) = bound_keys {
+        if let Some(min) = var.min {
+            args.push(format!("{}={}", min_key, format_bound(min)));
+        }
+        if let Some(max) = var.max {
+            args.push(format!("{}={}", max_key, format_bound(max)));
+        }
+    }
+
+    if let (Some(pattern), true) = (&var.pattern, is_str) {
+        args.push(format!("pattern={}", quote(&format!("^(?:{})$", pattern))));
+    }
+
+    if let Some(deprecated) = &var.deprecated {
+        if deprecated.is_empty() {
+            args.push("deprecated=True".to_string());
+        } else {
+            args.push(format!("deprecated={}", quote(deprecated)));
+        }
+    }
+
+    let name = var.name.to_lowercase();
+    match args.as_slice() {
+        [] => format!("{}: {}", name, type_hint),
+        [only] if only == "None" => format!("{}: {} = None", name, type_hint),
+        _ => format!("{}: {} = Field({})", name, type_hint, args.join(", ")),
+    }
+}
+
+fn type_hint(var: &EnvVar) -> String {
+    if !var.allowed_values.is_empty() {
+        let values: Vec<String> = var.allowed_values.iter().map(|v| quote(v)).collect();
+        return format!("Literal[{}]", values.join(", "));
+    }
+
+    match var.var_type {
+        EnvType::String if is_secret_str(var) => "SecretStr",
+        EnvType::String => "str",
+        EnvType::Url => "AnyUrl",
+        EnvType::Boolean => "bool",
+        EnvType::Integer => "int",
+        EnvType::Number => "float",
+    }
+    .to_string()
+}
+
+fn is_secret_str(var: &EnvVar) -> bool {
+    var.secret && var.var_type == EnvType::String && var.allowed_values.is_empty()
+}
+
+fn default_literal(var: &EnvVar, value: &str) -> String {
+    match literal(&var.var_type, value) {
+        Literal::Bool(true) => "True".to_string(),
+        Literal::Bool(false) => "False".to_string(),
+        Literal::Int(n) => n.to_string(),
+        Literal::Float(n) => format!("{:?}", n),
+        Literal::Str(s) => quote(s),
+    }
+}
@@ -0,0 +1,150 @@
+use crate::types::{EnvType, EnvVar};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+const HELPERS: &str = r#"#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    Missing(&'static str),
+    Invalid { name: &'static str, value: String },
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvError::Missing(name) => write!(f, "missing required environment variable {}", name),
+            EnvError::Invalid { name, value } => write!(f, "invalid value for environment variable {}: {:?}", name, value),
+        }
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+#[allow(dead_code)]
+fn get(name: &'static str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+#[allow(dead_code)]
+fn required(name: &'static str) -> Result<String, EnvError> {
+    get(name).ok_or(EnvError::Missing(name))
+}
+
+#[allow(dead_code)]
+fn parse<T: FromStr>(name: &'static str, value: String) -> Result<T, EnvError> {
+    value.parse().map_err(|_| EnvError::Invalid { name, value })
+}
+
+#[allow(dead_code)]
+fn parse_bool(name: &'static str, value: String) -> Result<bool, EnvError> {
+    match value.to_lowercase().as_str() {
+        "true" | "1" | "yes" => Ok(true),
+        "false" | "0" | "no" => Ok(false),
+        _ => Err(EnvError::Invalid { name, value }),
+    }
+}
+"#;
+
+/// Renders a Rust module with an `Env` struct and a `from_env` constructor
+/// that reads and parses every variable, without extra dependencies.
+pub fn render_rust_struct(vars: &[EnvVar]) -> String {
+    let mut content = String::from("use std::env;\nuse std::fmt;\nuse std::str::FromStr;\n\n");
+
+    content.push_str("#[derive(Debug, Clone)]\npub struct Env {\n");
+    for var in vars {
+        for line in doc_lines(var) {
+            content.push_str(&format!("    ///{}{}\n", if line.is_empty() { "" } else { " " }, line));
+        }
+        if var.deprecated.is_some() {
+            content.push_str("    #[deprecated]\n");
+        }
+        content.push_str(&format!("    pub {}: {},\n", field_name(&var.name), field_type(var)));
+    }
+    content.push_str("}\n\n");
+
+    content.push_str("impl Env {\n");
+    content.push_str("    /// Reads all variables from the process environment.\n");
+    content.push_str("    #[allow(deprecated)]\n");
+    content.push_str("    pub fn from_env() -> Result<Self, EnvError> {\n        Ok(Self {\n");
+    for var in vars {
+        content.push_str(&format!("            {}: {},\n", field_name(&var.name), field_value(var)));
+    }
+    content.push_str("        })\n    }\n}\n\n");
+
+    content.push_str(HELPERS);
+    content
+}
+
+fn field_name(name: &str) -> String {
+    let name = name.to_lowercase();
+    if KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
+
+fn value_type(var: &EnvVar) -> &'static str {
+    match var.var_type {
+        EnvType::String | EnvType::Url => "String",
+        EnvType::Boolean => "bool",
+        EnvType::Integer => "i64",
+        EnvType::Number => "f64",
+    }
+}
+
+fn field_type(var: &EnvVar) -> String {
+    if var.optional && var.default.is_none() {
+        format!("Option<{}>", value_type(var))
+    } else {
+        value_type(var).to_string()
+    }
+}
+
+fn field_value(var: &EnvVar) -> String {
+    let name = format!("{:?}", var.name);
+    let convert = |value: &str| match var.var_type {
+        EnvType::String | EnvType::Url => value.to_string(),
+        EnvType::Boolean => format!("parse_bool({}, {})?", name, value),
+        EnvType::Integer | EnvType::Number => format!("parse({}, {})?", name, value),
+    };
+
+    match &var.default {
+        Some(default) => convert(&format!("get({}).unwrap_or_else(|| {:?}.to_string())", name, default)),
+        None if var.optional => match var.var_type {
+            EnvType::String | EnvType::Url => format!("get({})", name),
+            EnvType::Boolean => format!("get({}).map(|v| parse_bool({}, v)).transpose()?", name, name),
+            EnvType::Integer | EnvType::Number => format!("get({}).map(|v| parse({}, v)).transpose()?", name, name),
+        },
+        None => convert(&format!("required({})?", name)),
+    }
+}
+
+fn doc_lines(var: &EnvVar) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if let Some(description) = &var.description {
+        lines.push(description.clone());
+    }
+    if !var.allowed_values.is_empty() {
+        lines.push(format!("One of: {}", var.allowed_values.join(", ")));
+    }
+    if let Some(default) = &var.default {
+        lines.push(format!("Default: `{}`", default));
+    }
+    if let Some(deprecated) = var.deprecated.as_deref().filter(|d| !d.is_empty()) {
+        lines.push(format!("Deprecated: {}", deprecated));
+    }
+
+    let name_line = format!("`{}`", var.name);
+    if lines.is_empty() {
+        vec![name_line]
+    } else {
+        lines.insert(0, name_line);
+        lines.insert(1, String::new());
+        lines
+    }
+}
@@ -0,0 +1,86 @@
+use crate::codegen::{literal, Literal};
+use crate::types::{EnvType, EnvVar};
+use serde_yaml::{Mapping, Value};
+
+const SCHEMA_MODELINE: &str =
+    "# yaml-language-server: $schema=https://raw.githubusercontent.com/DCC-BS/env-checker/main/lsp/schemas/env-schema.json\n";
+
+/// Renders an `env.schema.yml`, keeping the order of `vars`.
+pub fn render_yaml_schema(vars: &[EnvVar]) -> String {
+    let mut variables = Mapping::new();
+
+    for var in vars {
+        variables.insert(Value::from(var.name.as_str()), Value::Mapping(variable(var)));
+    }
+
+    let mut document = Mapping::new();
+    document.insert(Value::from("variables"), Value::Mapping(variables));
+
+    let yaml = serde_yaml::to_string(&document).unwrap_or_default();
+    format!("{}{}", SCHEMA_MODELINE, yaml)
+}
+
+fn variable(var: &EnvVar) -> Mapping {
+    let mut map = Mapping::new();
+    let mut set = |key: &str, value: Value| {
+        map.insert(Value::from(key), value);
+    };
+
+    set("type", Value::from(var.var_type.name()));
+    if let Some(description) = &var.description {
+        set("description", Value::from(description.as_str()));
+    }
+    if let Some(default) = &var.default {
+        set("default", scalar(&var.var_type, default));
+    }
+    if !var.optional {
+        set("required", Value::from(true));
+    } else if !var.required_in.is_empty() {
+        let profiles = var.required_in.iter().map(|p| Value::from(p.as_str())).collect();
+        set("required_in", Value::Sequence(profiles));
+    }
+    if let Some(group) = &var.group {
+        set("group", Value::from(group.as_str()));
+    }
+    if let Some(example) = &var.example {
+        set("example", scalar(&var.var_type, example));
+    }
+    if !var.allowed_values.is_empty() {
+        let values = var.allowed_values.iter().map(|v| scalar(&var.var_type, v)).collect();
+        set("enum", Value::Sequence(values));
+    }
+    if let Some(pattern) = &var.pattern {
+        set("pattern", Value::from(pattern.as_str()));
+    }
+    if let Some(min) = var.min {
+        set("min", number(min));
+    }
+    if let Some(max) = var.max {
+        set("max", number(max));
+    }
+    if var.secret {
+        set("secret", Value::from(true));
+    }
+    if let Some(deprecated) = &var.deprecated {
+        set("deprecated", Value::from(deprecated.as_str()));
+    }
+
+    map
+}
+
+fn scalar(var_type: &EnvType, value: &str) -> Value {
+    match literal(var_type, value) {
+        Literal::Bool(b) => Value::from(b),
+        Literal::Int(n) => Value::from(n),
+        Literal::Float(n) => Value::from(n),
+        Literal::Str(s) => Value::from(s),
+    }
+}
+
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        Value::from(value as i64)
+    } else {
+        Value::from(value)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::codegen::{format_bound, literal, quote, Literal};
+use crate::types::{EnvType, EnvVar};
+
+/// Renders a TypeScript module exporting the variables as a Zod object and
+/// the environment parsed with it.
+///
+/// Groups are attached through a Zod registry, the same way the Zod parser
+/// reads them back.
+pub fn render_zod_schema(vars: &[EnvVar]) -> String {
+    let mut content = String::from("import { z } from \"zod\";\n\n");
+
+    let has_groups = vars.iter().any(|v| v.group.is_some());
+    if has_groups {
+        content.push_str("export const envRegistry = z.registry<{ group: string }>();\n\n");
+    }
+
+    content.push_str("export const envSchema = z.object({\n");
+    for var in vars {
+        content.push_str(&format!("    {}: {},\n", var.name, property_chain(var)));
+    }
+    content.push_str("});\n\n");
+    content.push_str("export type Env = z.infer<typeof envSchema>;\n");
+    content.push_str("\nexport const env = envSchema.parse(process.env);\n");
+
+    content
+}
+
+fn property_chain(var: &EnvVar) -> String {
+    let mut chain = base_type(var);
+    let is_string = matches!(var.var_type, EnvType::String | EnvType::Url) && var.allowed_values.is_empty();
+    let is_number = matches!(var.var_type, EnvType::Integer | EnvType::Number);
+
+    if is_string || is_number {
+        if let Some(min) = var.min {
+            chain.push_str(&format!(".min({})", format_bound(min)));
+        }
+        if let Some(max) = var.max {
+            chain.push_str(&format!(".max({})", format_bound(max)));
+        }
+    }
+
+    if let (Some(pattern), true) = (&var.pattern, is_string) {
+        chain.push_str(&format!(".regex(new RegExp({}))", quote(&format!("^(?:{})$", pattern))));
+    }
+
+    match &var.default {
+        Some(default) => chain.push_str(&format!(".default({})", default_literal(var, default))),
+        None if var.optional => chain.push_str(".optional()"),
+        None => {}
+    }
+
+    let description = match &var.deprecated {
+        Some(message) if message.is_empty() => Some("@deprecated".to_string()),
+        Some(message) => Some(format!("@deprecated {}", message)),
+        None => var.description.clone(),
+    };
+    if let Some(description) = description {
+        chain.push_str(&format!("\n        .describe({})", quote(&description)));
+    }
+
+    if let Some(group) = &var.group {
+        chain.push_str(&format!("\n        .register(envRegistry, {{ group: {} }})", quote(group)));
+    }
+
+    chain
+}
+
+fn base_type(var: &EnvVar) -> String {
+    if !var.allowed_values.is_empty() {
+        let values: Vec<String> = var.allowed_values.iter().map(|v| quote(v)).collect();
+        return format!("z.enum([{}])", values.join(", "));
+    }
+
+    match var.var_type {
+        EnvType::String => "z.string()",
+        EnvType::Url => "z.url()",
+        EnvType::Boolean => "z.stringbool()",
+        EnvType::Integer => "z.coerce.number().int()",
+        EnvType::Number => "z.coerce.number()",
+    }
+    .to_string()
+}
+
+fn default_literal(var: &EnvVar, value: &str) -> String {
+    match literal(&var.var_type, value) {
+        Literal::Bool(b) => b.to_string(),
+        Literal::Int(n) => n.to_string(),
+        Literal::Float(n) => n.to_string(),
+        Literal::Str(s) => quote(s),
+    }
+}
@@ -1,143 +1,355 @@
-use crate::types::{Config, SchemaSource};
+use crate::types::{Config, EnvVar, KindDiscovery, RequiredOverride, SchemaKind, SchemaSource};
+use crate::schema::detect;
+use crate::walk;
 use anyhow::{Context, Result};
-use glob::glob;
+use glob::{glob, Pattern};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use tracing::warn;
 
+/// Project configuration files, lowest precedence first.
+pub const CONFIG_FILE_NAMES: &[&str] = &[".envchecker.yml", ".envchecker.yaml", ".envchecker.toml", ".envchecker.json"];
+
+/// File names of the user-global configuration, looked up in
+/// `$XDG_CONFIG_HOME/env-checker`; the first one found is used.
+const USER_CONFIG_FILE_NAMES: &[&str] = &["config.json", "config.toml", "config.yaml", "config.yml"];
+
+/// Loads the configuration of a project directory.
+///
+/// Sources are merged from lowest to highest precedence: the user-global
+/// config, the `"envChecker"` key of `package.json`, the `[tool.env-checker]`
+/// table of `pyproject.toml`, then `.envchecker.yml`/`.yaml`, `.toml` and
+/// `.json`. Objects merge key by key; any other value from a later source
+/// replaces the earlier one. Returns `None` when there is no source at all.
 pub fn load_config(workspace_root: &Path) -> Result<Option<Config>> {
-    let config_path = workspace_root.join(".envchecker.json");
-    
-    if !config_path.exists() {
+    let mut layers = Vec::new();
+
+    if let Some(dir) = user_config_dir() {
+        for name in USER_CONFIG_FILE_NAMES {
+            if let Some(layer) = read_config_file(&dir.join(name))? {
+                layers.push(layer);
+                break;
+            }
+        }
+    }
+    layers.extend(project_config_layers(workspace_root)?);
+
+    if layers.is_empty() {
         return Ok(None);
     }
 
-    let content = fs::read_to_string(&config_path)
-        .context("Failed to read .envchecker.json")?;
-    
-    let config: Config = serde_json::from_str(&content)
-        .context("Failed to parse .envchecker.json")?;
-    
+    let mut merged = serde_json::Value::Object(Default::default());
+    for layer in layers {
+        merge_values(&mut merged, normalize_keys(layer));
+    }
+
+    let config: Config = serde_json::from_value(merged)
+        .with_context(|| format!("Invalid configuration in {}", workspace_root.display()))?;
+
     Ok(Some(config))
 }
 
+/// Whether `dir` has configuration of its own, as opposed to only inheriting
+/// the user-global one. A broken config file still counts.
+pub fn has_project_config(dir: &Path) -> bool {
+    project_config_layers(dir).map_or(true, |layers| !layers.is_empty())
+}
+
+/// The configuration sources found in `dir`, lowest precedence first.
+fn project_config_layers(dir: &Path) -> Result<Vec<serde_json::Value>> {
+    let mut layers = Vec::new();
+
+    // Manifests belong to other tools, so one that fails to parse is not
+    // an error of ours.
+    if let Ok(content) = fs::read_to_string(dir.join("package.json")) {
+        match serde_json::from_str::<serde_json::Value>(&content) {
+            Ok(mut manifest) => layers.extend(manifest.get_mut("envChecker").map(serde_json::Value::take)),
+            Err(e) => warn!("Ignoring {}: {}", dir.join("package.json").display(), e),
+        }
+    }
+    if let Ok(content) = fs::read_to_string(dir.join("pyproject.toml")) {
+        match toml::from_str::<serde_json::Value>(&content) {
+            Ok(mut manifest) => layers.extend(
+                manifest
+                    .get_mut("tool")
+                    .and_then(|tool| tool.get_mut("env-checker"))
+                    .map(serde_json::Value::take),
+            ),
+            Err(e) => warn!("Ignoring {}: {}", dir.join("pyproject.toml").display(), e),
+        }
+    }
+
+    for name in CONFIG_FILE_NAMES {
+        layers.extend(read_config_file(&dir.join(name))?);
+    }
+
+    Ok(layers)
+}
+
+/// Reads a JSON, TOML or YAML config file, picked by extension.
+fn read_config_file(path: &Path) -> Result<Option<serde_json::Value>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", name))?;
+
+    let value = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&content).with_context(|| format!("Failed to parse {}", name))?,
+        Some("yml") | Some("yaml") => {
+            serde_yaml::from_str(&content).with_context(|| format!("Failed to parse {}", name))?
+        }
+        _ => serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", name))?,
+    };
+
+    Ok(Some(value))
+}
+
+/// `$XDG_CONFIG_HOME/env-checker`, defaulting to `~/.config/env-checker`.
+fn user_config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(base.join("env-checker"))
+}
+
+/// Keys of maps whose own keys are names chosen by the user, such as groups,
+/// profiles and variables, and are kept as written.
+const NAME_KEYED: &[&str] = &["groups", "profiles", "required", "requires", "requires_if"];
+
+/// Rewrites `camelCase` and `kebab-case` keys to `snake_case` at every
+/// level, so sources written in different styles merge into the same
+/// fields. Maps keyed by names are left untouched.
+fn normalize_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let key = snake_case(&key);
+                    if NAME_KEYED.contains(&key.as_str()) {
+                        (key, value)
+                    } else {
+                        (key, normalize_keys(value))
+                    }
+                })
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(normalize_keys).collect()),
+        value => value,
+    }
+}
+
+fn snake_case(key: &str) -> String {
+    let mut snake = String::with_capacity(key.len());
+    for ch in key.chars() {
+        match ch {
+            '-' => snake.push('_'),
+            ch if ch.is_ascii_uppercase() => {
+                snake.push('_');
+                snake.push(ch.to_ascii_lowercase());
+            }
+            ch => snake.push(ch),
+        }
+    }
+    snake
+}
+
+fn merge_values(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
 pub fn discover_schemas(workspace_root: &Path, config: &Config) -> Result<Vec<SchemaSource>> {
     let mut schemas = Vec::new();
 
     if config.auto_discover {
-        schemas.extend(discover_zod_schemas(workspace_root)?);
-        schemas.extend(discover_pydantic_schemas(workspace_root)?);
-        schemas.extend(discover_yaml_schemas(workspace_root)?);
+        let files = walk::walk_files(workspace_root, &config.exclude);
+        for kind in SchemaKind::ALL {
+            schemas.extend(discover_kind(workspace_root, &files, kind, config.discovery.for_kind(kind)));
+        }
     }
 
-    for schema_path in &config.schema_files {
-        let full_path = workspace_root.join(schema_path);
+    for entry in &config.schema_files {
+        let full_path = workspace_root.join(entry.path());
         if !full_path.exists() {
+            warn!("Configured schema file does not exist: {}", full_path.display());
             continue;
         }
-
-        let source = match full_path.extension().and_then(|e| e.to_str()) {
-            Some("ts") | Some("js") | Some("tsx") | Some("jsx") => SchemaSource::Zod {
-                file_path: full_path.to_string_lossy().to_string(),
-            },
-            Some("py") => SchemaSource::Pydantic {
-                file_path: full_path.to_string_lossy().to_string(),
-            },
-            Some("yml") | Some("yaml") => SchemaSource::Yaml {
-                file_path: full_path.to_string_lossy().to_string(),
-            },
-            _ => continue,
+        let Some(kind) = entry.kind() else {
+            warn!("Cannot tell the schema kind of {}", full_path.display());
+            continue;
         };
 
-        schemas.push(source);
+        let source = SchemaSource::new(kind, full_path.to_string_lossy().to_string());
+        if !schemas.contains(&source) {
+            schemas.push(source);
+        }
     }
 
     Ok(schemas)
 }
 
-fn discover_zod_schemas(workspace_root: &Path) -> Result<Vec<SchemaSource>> {
-    let mut schemas = Vec::new();
-    let patterns = vec![
-        "**/schema.ts",
-        "**/schema.js",
-        "**/config.ts",
-        "**/config.js",
-        "**/env.ts",
-        "**/env.js",
-        "**/*config.ts",
-        "**/*config.js",
-    ];
+/// Discovers the schemas of one kind among the walked `files`.
+///
+/// Zod and Pydantic candidates must also import the schema library; YAML
+/// schemas are recognised by name unless `include` says otherwise.
+fn discover_kind(workspace_root: &Path, files: &[PathBuf], kind: SchemaKind, settings: &KindDiscovery) -> Vec<SchemaSource> {
+    if !settings.enabled {
+        return Vec::new();
+    }
 
-    for pattern in patterns {
-        let full_pattern = workspace_root.join(pattern).to_string_lossy().to_string();
-        if let Ok(entries) = glob(&full_pattern) {
-            for entry in entries.flatten() {
-                if entry.is_file() {
-                    schemas.push(SchemaSource::Zod {
-                        file_path: entry.to_string_lossy().to_string(),
-                    });
-                }
+    let candidates = match (kind, settings.include.is_empty()) {
+        (SchemaKind::Yaml, true) => walk::match_files(files, workspace_root, YAML_PATTERNS),
+        (_, true) => files.iter().map(|f| f.to_string_lossy().to_string()).collect(),
+        (_, false) => walk::match_files(files, workspace_root, &settings.include),
+    };
+    let exclude: Vec<Pattern> = settings.exclude.iter().filter_map(|p| Pattern::new(p).ok()).collect();
+
+    candidates
+        .into_iter()
+        .filter(|file_path| {
+            let relative = Path::new(file_path).strip_prefix(workspace_root).unwrap_or(Path::new(file_path));
+            !exclude.iter().any(|p| p.matches_path_with(relative, walk::MATCH_OPTIONS))
+        })
+        .filter_map(|file_path| match kind {
+            SchemaKind::Yaml => Some(SchemaSource::Yaml { file_path }),
+            _ => detect::detect_schema(Path::new(&file_path)).filter(|source| source.kind() == kind),
+        })
+        .collect()
+}
+
+/// Replaces the requirement of schema variables listed in the `required`
+/// configuration.
+pub fn apply_required_overrides(config: &Config, vars: &mut [EnvVar]) {
+    for var in vars {
+        match config.required.get(&var.name) {
+            Some(RequiredOverride::Always(required)) => {
+                var.optional = !required;
+                var.required_in.clear();
             }
+            Some(RequiredOverride::InProfiles(profiles)) => {
+                var.optional = true;
+                var.required_in = profiles.clone();
+            }
+            None => {}
         }
     }
-
-    Ok(schemas)
 }
 
-fn discover_pydantic_schemas(workspace_root: &Path) -> Result<Vec<SchemaSource>> {
-    let mut schemas = Vec::new();
-    let patterns = vec![
-        "**/config.py",
-        "**/settings.py",
-        "**/env.py",
-        "**/configuration.py",
-        "**/app_config.py",
-    ];
+/// Files auto-discovered as YAML schemas.
+pub const YAML_PATTERNS: &[&str] = &[
+    "**/env.schema.yml",
+    "**/env.schema.yaml",
+    "**/.env.schema.yml",
+    "**/.env.schema.yaml",
+];
 
-    for pattern in patterns {
-        let full_pattern = workspace_root.join(pattern).to_string_lossy().to_string();
-        if let Ok(entries) = glob(&full_pattern) {
-            for entry in entries.flatten() {
-                if entry.is_file() {
-                    schemas.push(SchemaSource::Pydantic {
-                        file_path: entry.to_string_lossy().to_string(),
-                    });
-                }
-            }
+/// Glob patterns, relative to the workspace root, for every file whose
+/// change affects diagnostics: schemas, env files and the config itself.
+pub fn watch_patterns(config: &Config) -> Vec<String> {
+    let mut patterns: Vec<String> = CONFIG_FILE_NAMES.iter().map(|name| name.to_string()).collect();
+
+    if config.auto_discover {
+        let discovery = &config.discovery;
+        if discovery.zod.enabled {
+            patterns.push(format!("**/*.{{{}}}", detect::ZOD_EXTENSIONS.join(",")));
+        }
+        if discovery.pydantic.enabled {
+            patterns.push("**/*.py".to_string());
+        }
+        if discovery.yaml.enabled {
+            patterns.extend(YAML_PATTERNS.iter().map(|p| p.to_string()));
         }
     }
+    // Base schemas pulled in through `extends`.
+    patterns.push("**/*.schema.{yml,yaml}".to_string());
 
-    Ok(schemas)
+    patterns.extend(["**/.env", "**/.env.*", "**/*.env"].map(String::from));
+    let configured = config
+        .schema_files
+        .iter()
+        .map(|entry| entry.path().to_string())
+        .chain(config.discovery.yaml.include.iter().cloned())
+        .chain(config.env_files.iter().cloned())
+        .chain(config.profiles.values().flatten().cloned());
+    for pattern in configured {
+        let pattern = pattern.trim_start_matches("./");
+        let pattern = if pattern.starts_with("**/") {
+            pattern.to_string()
+        } else {
+            format!("**/{}", pattern)
+        };
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+
+    patterns
 }
 
-fn discover_yaml_schemas(workspace_root: &Path) -> Result<Vec<SchemaSource>> {
-    let mut schemas = Vec::new();
-    let patterns = vec![
-        "**/env.schema.yml",
-        "**/env.schema.yaml",
-        "**/.env.schema.yml",
-        "**/.env.schema.yaml",
-    ];
+pub fn get_env_file_paths(workspace_root: &Path, config: &Config) -> Result<Vec<String>> {
+    let mut env_files = resolve_env_patterns(workspace_root, &config.env_files);
 
-    for pattern in patterns {
-        let full_pattern = workspace_root.join(pattern).to_string_lossy().to_string();
-        if let Ok(entries) = glob(&full_pattern) {
-            for entry in entries.flatten() {
-                if entry.is_file() {
-                    schemas.push(SchemaSource::Yaml {
-                        file_path: entry.to_string_lossy().to_string(),
-                    });
-                }
+    for files in config.profiles.values() {
+        for path in resolve_env_patterns(workspace_root, files) {
+            if !env_files.contains(&path) {
+                env_files.push(path);
             }
         }
     }
 
-    Ok(schemas)
+    if env_files.is_empty() {
+        let default_path = workspace_root.join(".env");
+        if default_path.exists() {
+            env_files.push(default_path.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(env_files)
 }
 
-pub fn get_env_file_paths(workspace_root: &Path, config: &Config) -> Result<Vec<String>> {
+/// Returns the existing files of the profile stack, lowest layer first.
+pub fn get_profile_file_paths(workspace_root: &Path, config: &Config, profile: &str) -> Vec<String> {
+    config
+        .profiles
+        .get(profile)
+        .map(|files| resolve_env_patterns(workspace_root, files))
+        .unwrap_or_default()
+}
+
+/// Returns the names of all profiles whose file stack contains `file_path`.
+pub fn profiles_containing(workspace_root: &Path, config: &Config, file_path: &Path) -> Vec<String> {
+    let file_path = file_path.to_string_lossy();
+
+    config
+        .profiles
+        .keys()
+        .filter(|name| {
+            get_profile_file_paths(workspace_root, config, name)
+                .iter()
+                .any(|p| *p == file_path)
+        })
+        .cloned()
+        .collect()
+}
+
+fn resolve_env_patterns(workspace_root: &Path, patterns: &[String]) -> Vec<String> {
     let mut env_files = Vec::new();
 
-    for pattern in &config.env_files {
+    for pattern in patterns {
         let full_path = workspace_root.join(pattern);
         
         if full_path.exists() && full_path.is_file() {
@@ -154,12 +366,85 @@ pub fn get_env_file_paths(workspace_root: &Path, config: &Config) -> Result<Vec<
         }
     }
 
-    if env_files.is_empty() {
-        let default_path = workspace_root.join(".env");
-        if default_path.exists() {
-            env_files.push(default_path.to_string_lossy().to_string());
-        }
+    env_files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+    use serde_json::json;
+
+    #[test]
+    fn normalizes_nested_keys_but_not_names() {
+        let normalized = normalize_keys(json!({
+            "schemaFiles": [{ "path": "env.ts", "kind": "zod" }],
+            "discovery": { "zod": { "enabled": false } },
+            "auto-discover": false,
+            "rules": {
+                "requires-if": { "STORAGE_DRIVER": { "s3": ["S3_BUCKET"] } },
+                "oneOf": [["API_KEY", "API_TOKEN"]]
+            },
+            "groups": { "apiKeys": { "title": "API keys" } },
+            "profiles": { "prodEU": [".env.production"] },
+            "required": { "SENTRY_DSN": ["prodEU"] }
+        }));
+
+        assert_eq!(
+            normalized,
+            json!({
+                "schema_files": [{ "path": "env.ts", "kind": "zod" }],
+                "discovery": { "zod": { "enabled": false } },
+                "auto_discover": false,
+                "rules": {
+                    "requires_if": { "STORAGE_DRIVER": { "s3": ["S3_BUCKET"] } },
+                    "one_of": [["API_KEY", "API_TOKEN"]]
+                },
+                "groups": { "apiKeys": { "title": "API keys" } },
+                "profiles": { "prodEU": [".env.production"] },
+                "required": { "SENTRY_DSN": ["prodEU"] }
+            })
+        );
+
+        let config: Config = serde_json::from_value(normalized).unwrap();
+        assert_eq!(config.rules.requires_if["STORAGE_DRIVER"]["s3"], ["S3_BUCKET"]);
+        assert_eq!(config.rules.one_of.len(), 1);
+        assert!(config.profiles.contains_key("prodEU"));
     }
 
-    Ok(env_files)
+    /// The only test that touches `XDG_CONFIG_HOME`, so parallel tests do
+    /// not race on it.
+    #[test]
+    fn later_sources_take_precedence() {
+        let dir = TempDir::new("config-precedence");
+        std::env::set_var("XDG_CONFIG_HOME", dir.path().join("xdg"));
+        dir.write("xdg/env-checker/config.toml", "exclude = [\"dist\"]\nenv-files = [\".env.user\"]\n\n[groups]\nDatabase = \"User database\"\n");
+        dir.write("xdg/env-checker/config.yml", "exclude: [ignored]\n");
+
+        let project = dir.path().join("project");
+        dir.write("project/package.json", r#"{ "name": "app", "envChecker": { "envFiles": [".env.package"], "groups": { "Cache": "Cache" } } }"#);
+        dir.write("project/pyproject.toml", "[tool.env-checker]\nenv-files = [\".env.pyproject\"]\nauto-discover = false\n");
+        dir.write("project/.envchecker.yml", "profiles:\n  production: [.env.production]\n");
+        dir.write("project/.envchecker.toml", "[profiles]\nstaging = [\".env.staging\"]\n");
+        dir.write("project/.envchecker.json", r#"{ "envFiles": [".env"], "groups": { "Database": "Project database" } }"#);
+
+        let config = load_config(&project).unwrap().unwrap();
+        assert_eq!(config.exclude, ["dist"]);
+        assert_eq!(config.env_files, [".env"]);
+        assert!(!config.auto_discover);
+        let groups: Vec<_> = config.groups.iter().map(|(name, group)| (name.as_str(), group.title())).collect();
+        assert_eq!(groups, [("Database", Some("Project database")), ("Cache", Some("Cache"))]);
+        let profiles: Vec<_> = config.profiles.keys().map(String::as_str).collect();
+        assert_eq!(profiles, ["production", "staging"]);
+
+        // Only the user-global config: no project configuration of its own.
+        let empty = dir.path().join("empty");
+        fs::create_dir_all(&empty).unwrap();
+        assert!(!has_project_config(&empty));
+        assert_eq!(load_config(&empty).unwrap().unwrap().exclude, ["dist"]);
+
+        std::env::set_var("XDG_CONFIG_HOME", dir.path().join("missing"));
+        assert!(load_config(&empty).unwrap().is_none());
+        assert!(has_project_config(&project));
+    }
 }
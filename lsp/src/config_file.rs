@@ -0,0 +1,780 @@
+use crate::diagnostics;
+use crate::document;
+use crate::types::SchemaKind;
+use std::path::Path;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, Documentation, Position,
+};
+
+/// The name of the configuration file, in the workspace root or a package.
+pub const CONFIG_FILE_NAME: &str = ".envchecker.json";
+
+/// Whether `path` is checked and completed as it is edited. Only the JSON
+/// file is: YAML, TOML and manifest sources are read, but errors in them
+/// are only logged by the server and reported by `check`.
+pub fn is_config_file(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| n == CONFIG_FILE_NAME)
+}
+
+/// The expected shape of a configuration value.
+#[derive(Debug, Clone, Copy)]
+enum Shape {
+    Bool,
+    String,
+    StringArray,
+    Groups,
+    StringArrayMap,
+    StringArrayArray,
+    ConditionalMap,
+    RequiredMap,
+    SchemaFiles,
+    Kind,
+    Object(&'static [KeySpec]),
+}
+
+impl Shape {
+    fn describe(self) -> &'static str {
+        match self {
+            Shape::Bool => "a boolean",
+            Shape::String => "a string",
+            Shape::StringArray => "an array of strings",
+            Shape::Groups => "an object of titles or { \"title\", \"description\" } objects",
+            Shape::StringArrayMap => "an object of string arrays",
+            Shape::StringArrayArray => "an array of string arrays",
+            Shape::ConditionalMap => "an object of { value: [variables] } objects",
+            Shape::RequiredMap => "an object of booleans or arrays of profile names",
+            Shape::SchemaFiles => "an array of paths or { \"path\", \"kind\" } objects",
+            Shape::Kind => "one of \"zod\", \"pydantic\" or \"yaml\"",
+            Shape::Object(_) => "an object",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KeySpec {
+    name: &'static str,
+    /// The snake_case spelling, accepted as well.
+    alias: Option<&'static str>,
+    shape: Shape,
+    doc: &'static str,
+}
+
+const KIND_DISCOVERY_KEYS: &[KeySpec] = &[
+    KeySpec { name: "enabled", alias: None, shape: Shape::Bool, doc: "Whether this kind of schema is discovered (default: true)" },
+    KeySpec { name: "include", alias: None, shape: Shape::StringArray, doc: "Globs limiting the files considered for this kind" },
+    KeySpec { name: "exclude", alias: None, shape: Shape::StringArray, doc: "Globs of files never discovered as this kind" },
+];
+
+const DISCOVERY_KEYS: &[KeySpec] = &[
+    KeySpec { name: "zod", alias: None, shape: Shape::Object(KIND_DISCOVERY_KEYS), doc: "Discovery of Zod schemas" },
+    KeySpec { name: "pydantic", alias: None, shape: Shape::Object(KIND_DISCOVERY_KEYS), doc: "Discovery of Pydantic settings" },
+    KeySpec { name: "yaml", alias: None, shape: Shape::Object(KIND_DISCOVERY_KEYS), doc: "Discovery of YAML schemas" },
+];
+
+const GROUP_KEYS: &[KeySpec] = &[
+    KeySpec { name: "title", alias: None, shape: Shape::String, doc: "Display title of the group" },
+    KeySpec { name: "description", alias: None, shape: Shape::String, doc: "Description written below the group's title" },
+];
+
+const RULE_KEYS: &[KeySpec] = &[
+    KeySpec { name: "requires", alias: None, shape: Shape::StringArrayMap, doc: "Variables required whenever another one is set" },
+    KeySpec { name: "requiresIf", alias: Some("requires_if"), shape: Shape::ConditionalMap, doc: "Variables required when another one has a given value" },
+    KeySpec { name: "oneOf", alias: Some("one_of"), shape: Shape::StringArrayArray, doc: "Sets of variables of which exactly one has to be set" },
+    KeySpec { name: "mutuallyExclusive", alias: Some("mutually_exclusive"), shape: Shape::StringArrayArray, doc: "Sets of variables of which at most one may be set" },
+];
+
+const SCHEMA_FILE_KEYS: &[KeySpec] = &[
+    KeySpec { name: "path", alias: None, shape: Shape::String, doc: "Path of the schema file, relative to this file" },
+    KeySpec { name: "kind", alias: None, shape: Shape::Kind, doc: "Kind of schema: zod, pydantic or yaml" },
+];
+
+const CONFIG_KEYS: &[KeySpec] = &[
+    KeySpec { name: "schemaFiles", alias: Some("schema_files"), shape: Shape::SchemaFiles, doc: "Schema files used in addition to discovered ones" },
+    KeySpec { name: "envFiles", alias: Some("env_files"), shape: Shape::StringArray, doc: "Glob patterns of the env files to load (default: .env)" },
+    KeySpec { name: "autoDiscover", alias: Some("auto_discover"), shape: Shape::Bool, doc: "Discover schema files automatically (default: true)" },
+    KeySpec { name: "exclude", alias: None, shape: Shape::StringArray, doc: "Globs that schema discovery skips" },
+    KeySpec { name: "discovery", alias: None, shape: Shape::Object(DISCOVERY_KEYS), doc: "Discovery settings per schema kind" },
+    KeySpec { name: "groups", alias: None, shape: Shape::Groups, doc: "Titles, descriptions and order of variable groups" },
+    KeySpec { name: "profiles", alias: None, shape: Shape::StringArrayMap, doc: "Named env file stacks, validated separately" },
+    KeySpec { name: "required", alias: None, shape: Shape::RequiredMap, doc: "Whether variables are required: true, false or the profiles they are required in" },
+    KeySpec { name: "rules", alias: None, shape: Shape::Object(RULE_KEYS), doc: "Requirements between variables" },
+];
+
+/// Checks a configuration file for syntax errors, unknown keys, values of
+/// the wrong type and schema files that do not exist.
+pub fn check_config(content: &str, config_dir: &Path) -> Vec<Diagnostic> {
+    if let Err(e) = serde_json::from_str::<serde_json::Value>(content) {
+        let position = Position::new(e.line().saturating_sub(1) as u32, e.column().saturating_sub(1) as u32);
+        return vec![diagnostics::create_config_diagnostic(
+            tower_lsp::lsp_types::Range::new(position, position),
+            DiagnosticSeverity::ERROR,
+            "config-syntax",
+            format!("Invalid JSON: {}", e),
+        )];
+    }
+
+    let mut parser = Parser { text: content, pos: 0 };
+    let Some(root) = parser.value() else {
+        return Vec::new();
+    };
+
+    let mut checker = Checker {
+        text: content,
+        config_dir,
+        diagnostics: Vec::new(),
+    };
+    checker.check(&root, Shape::Object(CONFIG_KEYS), "configuration");
+    checker.diagnostics
+}
+
+/// Offers the keys allowed at the cursor, or values for booleans and
+/// schema kinds.
+pub fn complete_config(content: &str, position: Position) -> Vec<CompletionItem> {
+    let offset = document::offset_at(content, position);
+    let context = scan_context(&content[..offset]);
+
+    let Some(Frame { is_object: true, expect_key, pending_key, seen, .. }) = context.frames.last() else {
+        return Vec::new();
+    };
+    let path: Vec<&str> = context.frames.iter().skip(1).map(|f| f.key.as_deref().unwrap_or("[]")).collect();
+    let Some(keys) = keys_at(&path) else {
+        return Vec::new();
+    };
+
+    if *expect_key {
+        return keys
+            .iter()
+            .filter(|spec| !seen.iter().any(|s| s == spec.name || Some(s.as_str()) == spec.alias))
+            .map(|spec| CompletionItem {
+                label: spec.name.to_string(),
+                kind: Some(CompletionItemKind::PROPERTY),
+                detail: Some(spec.shape.describe().to_string()),
+                documentation: Some(Documentation::String(spec.doc.to_string())),
+                insert_text: Some(if context.in_string {
+                    spec.name.to_string()
+                } else {
+                    format!("\"{}\": ", spec.name)
+                }),
+                ..Default::default()
+            })
+            .collect();
+    }
+
+    let Some(spec) = pending_key.as_deref().and_then(|key| find_key(keys, key)) else {
+        return Vec::new();
+    };
+    let values: Vec<&str> = match spec.shape {
+        Shape::Bool if !context.in_string => vec!["true", "false"],
+        Shape::Kind => SchemaKind::ALL.iter().map(|k| k.name()).collect(),
+        _ => Vec::new(),
+    };
+
+    values
+        .into_iter()
+        .map(|value| CompletionItem {
+            label: value.to_string(),
+            kind: Some(CompletionItemKind::VALUE),
+            insert_text: Some(match spec.shape {
+                Shape::Kind if !context.in_string => format!("\"{}\"", value),
+                _ => value.to_string(),
+            }),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn find_key<'a>(keys: &'a [KeySpec], name: &str) -> Option<&'a KeySpec> {
+    keys.iter().find(|spec| spec.name == name || spec.alias == Some(name))
+}
+
+/// The keys allowed in the object at `path`, where `[]` stands for an
+/// array element.
+fn keys_at(path: &[&str]) -> Option<&'static [KeySpec]> {
+    let mut keys = CONFIG_KEYS;
+
+    for (index, segment) in path.iter().enumerate() {
+        let spec = find_key(keys, segment)?;
+        keys = match (spec.shape, path.get(index + 1)) {
+            (Shape::Object(next), _) => next,
+            (Shape::SchemaFiles, Some(&"[]")) if index + 2 == path.len() => return Some(SCHEMA_FILE_KEYS),
+            (Shape::Groups, Some(_)) if index + 2 == path.len() => return Some(GROUP_KEYS),
+            _ => return None,
+        };
+    }
+
+    Some(keys)
+}
+
+#[derive(Debug, Default)]
+struct Frame {
+    is_object: bool,
+    /// The key this container is the value of, `None` inside arrays.
+    key: Option<String>,
+    expect_key: bool,
+    pending_key: Option<String>,
+    seen: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct ScanContext {
+    frames: Vec<Frame>,
+    in_string: bool,
+}
+
+/// Tracks the enclosing objects of the end of `text`, tolerating the
+/// incomplete JSON that is being typed.
+fn scan_context(text: &str) -> ScanContext {
+    let mut context = ScanContext::default();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        match ch {
+            '"' => {
+                let mut end = None;
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => {
+                            end = Some(i);
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                let Some(end) = end else {
+                    context.in_string = true;
+                    break;
+                };
+                if let Some(frame) = context.frames.last_mut().filter(|f| f.is_object && f.expect_key) {
+                    let key = serde_json::from_str::<String>(&text[start..=end]).unwrap_or_default();
+                    frame.seen.push(key.clone());
+                    frame.pending_key = Some(key);
+                }
+            }
+            '{' | '[' => {
+                let key = context.frames.last().and_then(|f| if f.is_object { f.pending_key.clone() } else { None });
+                context.frames.push(Frame {
+                    is_object: ch == '{',
+                    key,
+                    expect_key: ch == '{',
+                    ..Default::default()
+                });
+            }
+            '}' | ']' => {
+                context.frames.pop();
+            }
+            ':' => {
+                if let Some(frame) = context.frames.last_mut() {
+                    frame.expect_key = false;
+                }
+            }
+            ',' => {
+                if let Some(frame) = context.frames.last_mut().filter(|f| f.is_object) {
+                    frame.expect_key = true;
+                    frame.pending_key = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    context
+}
+
+/// A JSON value with the byte range it spans in the source.
+#[derive(Debug)]
+enum Node {
+    Object { start: usize, end: usize, entries: Vec<Entry> },
+    Array { start: usize, end: usize, items: Vec<Node> },
+    String { start: usize, end: usize, value: String },
+    Bool { start: usize, end: usize },
+    Other { start: usize, end: usize },
+}
+
+#[derive(Debug)]
+struct Entry {
+    key: String,
+    key_start: usize,
+    key_end: usize,
+    value: Node,
+}
+
+impl Node {
+    fn span(&self) -> (usize, usize) {
+        match self {
+            Node::Object { start, end, .. }
+            | Node::Array { start, end, .. }
+            | Node::String { start, end, .. }
+            | Node::Bool { start, end }
+            | Node::Other { start, end } => (*start, *end),
+        }
+    }
+}
+
+/// Records the spans of a document that `serde_json` already accepted.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn value(&mut self) -> Option<Node> {
+        self.skip_whitespace();
+        let start = self.pos;
+
+        match self.text[self.pos..].chars().next()? {
+            '{' => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.text[self.pos..].starts_with('}') {
+                        self.pos += 1;
+                        break;
+                    }
+                    let Node::String { start: key_start, end: key_end, value: key } = self.value()? else {
+                        return None;
+                    };
+                    self.skip_whitespace();
+                    self.pos += 1; // ':'
+                    let value = self.value()?;
+                    entries.push(Entry { key, key_start, key_end, value });
+                    self.skip_whitespace();
+                    if self.text[self.pos..].starts_with(',') {
+                        self.pos += 1;
+                    }
+                }
+                Some(Node::Object { start, end: self.pos, entries })
+            }
+            '[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_whitespace();
+                    if self.text[self.pos..].starts_with(']') {
+                        self.pos += 1;
+                        break;
+                    }
+                    items.push(self.value()?);
+                    self.skip_whitespace();
+                    if self.text[self.pos..].starts_with(',') {
+                        self.pos += 1;
+                    }
+                }
+                Some(Node::Array { start, end: self.pos, items })
+            }
+            '"' => {
+                let mut escaped = false;
+                let length = self.text[self.pos + 1..].char_indices().find_map(|(i, c)| {
+                    let closes = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    closes.then_some(i)
+                })?;
+                self.pos += length + 2;
+                let value = serde_json::from_str(&self.text[start..self.pos]).ok()?;
+                Some(Node::String { start, end: self.pos, value })
+            }
+            _ => {
+                let rest = &self.text[self.pos..];
+                let length = rest.find(|c: char| c == ',' || c == '}' || c == ']' || c.is_whitespace()).unwrap_or(rest.len());
+                self.pos += length;
+                match &rest[..length] {
+                    "true" | "false" => Some(Node::Bool { start, end: self.pos }),
+                    _ => Some(Node::Other { start, end: self.pos }),
+                }
+            }
+        }
+    }
+}
+
+struct Checker<'a> {
+    text: &'a str,
+    config_dir: &'a Path,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, (start, end): (usize, usize), severity: DiagnosticSeverity, code: &str, message: String) {
+        let range = document::range_at(self.text, start, end);
+        self.diagnostics.push(diagnostics::create_config_diagnostic(range, severity, code, message));
+    }
+
+    fn wrong_type(&mut self, node: &Node, shape: Shape, name: &str) {
+        self.report(
+            node.span(),
+            DiagnosticSeverity::ERROR,
+            "config-invalid-type",
+            format!(
+                "'{}' must be {}; the configuration is not loaded until this is fixed",
+                name,
+                shape.describe()
+            ),
+        );
+    }
+
+    fn check(&mut self, node: &Node, shape: Shape, name: &str) {
+        match (shape, node) {
+            (Shape::Bool, Node::Bool { .. }) | (Shape::String, Node::String { .. }) => {}
+            (Shape::Kind, Node::String { value, .. }) if SchemaKind::ALL.iter().any(|k| k.name() == value) => {}
+            (Shape::StringArray, Node::Array { items, .. }) => {
+                for item in items.iter().filter(|item| !matches!(item, Node::String { .. })) {
+                    self.wrong_type(item, shape, name);
+                }
+            }
+            (Shape::Groups, Node::Object { entries, .. }) => {
+                for entry in entries.iter().filter(|entry| !matches!(entry.value, Node::String { .. })) {
+                    match &entry.value {
+                        Node::Object { .. } => self.check(&entry.value, Shape::Object(GROUP_KEYS), &entry.key),
+                        _ => self.wrong_type(&entry.value, shape, name),
+                    }
+                }
+            }
+            (Shape::StringArrayMap, Node::Object { entries, .. }) => {
+                for entry in entries {
+                    self.check(&entry.value, Shape::StringArray, &entry.key);
+                }
+            }
+            (Shape::StringArrayArray, Node::Array { items, .. }) => {
+                for item in items {
+                    self.check(item, Shape::StringArray, name);
+                }
+            }
+            (Shape::ConditionalMap, Node::Object { entries, .. }) => {
+                for entry in entries {
+                    self.check(&entry.value, Shape::StringArrayMap, &entry.key);
+                }
+            }
+            (Shape::RequiredMap, Node::Object { entries, .. }) => {
+                for entry in entries.iter().filter(|entry| !matches!(entry.value, Node::Bool { .. })) {
+                    self.check(&entry.value, Shape::StringArray, &entry.key);
+                }
+            }
+            (Shape::SchemaFiles, Node::Array { items, .. }) => {
+                for item in items {
+                    self.check_schema_file(item, name);
+                }
+            }
+            (Shape::Object(keys), Node::Object { entries, .. }) => {
+                for entry in entries {
+                    match find_key(keys, &entry.key) {
+                        Some(spec) => self.check(&entry.value, spec.shape, &entry.key),
+                        None => self.unknown_key(entry, keys),
+                    }
+                }
+            }
+            _ => self.wrong_type(node, shape, name),
+        }
+    }
+
+    fn unknown_key(&mut self, entry: &Entry, keys: &[KeySpec]) {
+        let suggestion = keys
+            .iter()
+            .find(|spec| {
+                [Some(spec.name), spec.alias]
+                    .into_iter()
+                    .flatten()
+                    .any(|name| {
+                        let distance = edit_distance(&name.to_lowercase(), &entry.key.to_lowercase());
+                        distance <= 2 && distance * 3 <= name.len()
+                    })
+            })
+            .map(|spec| format!("; did you mean '{}'?", spec.name))
+            .unwrap_or_default();
+
+        self.report(
+            (entry.key_start, entry.key_end),
+            DiagnosticSeverity::WARNING,
+            "config-unknown-key",
+            format!("Unknown configuration key '{}'{}", entry.key, suggestion),
+        );
+    }
+
+    fn check_schema_file(&mut self, node: &Node, name: &str) {
+        let path = match node {
+            Node::String { value, .. } => {
+                if SchemaKind::from_extension(Path::new(value)).is_none() {
+                    self.report(
+                        node.span(),
+                        DiagnosticSeverity::WARNING,
+                        "config-unknown-kind",
+                        format!("Cannot tell the schema kind of '{}' from its extension; use {{ \"path\": \"{}\", \"kind\": ... }}", value, value),
+                    );
+                }
+                Some((value, node.span()))
+            }
+            Node::Object { entries, .. } => {
+                self.check(node, Shape::Object(SCHEMA_FILE_KEYS), name);
+                for required in ["path", "kind"] {
+                    if !entries.iter().any(|e| e.key == required) {
+                        self.report(
+                            node.span(),
+                            DiagnosticSeverity::ERROR,
+                            "config-invalid-type",
+                            format!("Schema file entries need a '{}'", required),
+                        );
+                    }
+                }
+                entries.iter().find(|e| e.key == "path").and_then(|e| match &e.value {
+                    Node::String { value, .. } => Some((value, e.value.span())),
+                    _ => None,
+                })
+            }
+            _ => {
+                self.wrong_type(node, Shape::SchemaFiles, name);
+                None
+            }
+        };
+
+        if let Some((path, span)) = path {
+            if !self.config_dir.join(path).exists() {
+                self.report(
+                    span,
+                    DiagnosticSeverity::WARNING,
+                    "config-missing-file",
+                    format!("Schema file '{}' does not exist", path),
+                );
+            }
+        }
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::NumberOrString;
+
+    /// A range as `(start line, start column, end line, end column)`.
+    type Span = (u32, u32, u32, u32);
+
+    /// The code, range and message of each diagnostic.
+    fn check(content: &str) -> Vec<(String, Span, String)> {
+        check_config(content, Path::new(env!("CARGO_MANIFEST_DIR")))
+            .into_iter()
+            .map(|d| {
+                let Some(NumberOrString::String(code)) = d.code else { panic!("diagnostic without code") };
+                let range = (d.range.start.line, d.range.start.character, d.range.end.line, d.range.end.character);
+                (code, range, d.message)
+            })
+            .collect()
+    }
+
+    fn codes(content: &str) -> Vec<String> {
+        check(content).into_iter().map(|(code, _, _)| code).collect()
+    }
+
+    /// Completes at the end of `content`.
+    fn complete(content: &str) -> Vec<(String, String)> {
+        let line = content.matches('\n').count() as u32;
+        let character = content.rsplit('\n').next().unwrap().encode_utf16().count() as u32;
+        complete_config(content, Position::new(line, character))
+            .into_iter()
+            .map(|item| (item.label, item.insert_text.unwrap_or_default()))
+            .collect()
+    }
+
+    fn labels(content: &str) -> Vec<String> {
+        complete(content).into_iter().map(|(label, _)| label).collect()
+    }
+
+    #[test]
+    fn accepts_every_documented_shape() {
+        let content = r#"{
+  "schemaFiles": ["env.schema.yml", { "path": "src/main.rs", "kind": "yaml" }],
+  "envFiles": [".env", ".env.*"],
+  "auto_discover": false,
+  "exclude": ["dist/**"],
+  "discovery": { "zod": { "enabled": false, "include": ["src/**"], "exclude": [] }, "yaml": {} },
+  "groups": { "db": "Database", "cache": { "title": "Cache", "description": "Redis" } },
+  "profiles": { "production": [".env", ".env.production"] },
+  "required": { "API_KEY": true, "SENTRY_DSN": ["production"] },
+  "rules": {
+    "requires": { "SMTP_HOST": ["SMTP_USER"] },
+    "requires_if": { "AUTH": { "oidc": ["OIDC_ISSUER"] } },
+    "oneOf": [["DATABASE_URL", "DB_HOST"]],
+    "mutuallyExclusive": [["A", "B"]]
+  }
+}"#;
+
+        // Only the schema file that does not exist is reported.
+        assert_eq!(codes(content), vec!["config-missing-file"]);
+    }
+
+    #[test]
+    fn reports_syntax_errors_alone() {
+        let diagnostics = check("{\n  \"envFiles\": [\".env\",],\n  \"bogus\": 1\n}");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].0, "config-syntax");
+        assert_eq!(diagnostics[0].1 .0, 1);
+    }
+
+    #[test]
+    fn reports_unknown_keys_on_the_key_with_a_suggestion() {
+        let diagnostics = check("{\n  \"envFile\": [],\n  \"discovery\": { \"zods\": {}, \"python\": {} }\n}");
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    "config-unknown-key".to_string(),
+                    (1, 2, 1, 11),
+                    "Unknown configuration key 'envFile'; did you mean 'envFiles'?".to_string()
+                ),
+                (
+                    "config-unknown-key".to_string(),
+                    (2, 17, 2, 23),
+                    "Unknown configuration key 'zods'; did you mean 'zod'?".to_string()
+                ),
+                (
+                    "config-unknown-key".to_string(),
+                    (2, 29, 2, 37),
+                    "Unknown configuration key 'python'".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_wrong_types_on_the_value() {
+        let content = "{\n  \"autoDiscover\": \"yes\",\n  \"envFiles\": [\".env\", 3],\n  \"discovery\": { \"zod\": { \"enabled\": 1 } }\n}";
+        let diagnostics = check(content);
+
+        let ranges: Vec<_> = diagnostics.iter().map(|(code, range, _)| (code.as_str(), *range)).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                ("config-invalid-type", (1, 18, 1, 23)),
+                ("config-invalid-type", (2, 23, 2, 24)),
+                ("config-invalid-type", (3, 37, 3, 38)),
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].2,
+            "'autoDiscover' must be a boolean; the configuration is not loaded until this is fixed"
+        );
+        assert!(diagnostics[1].2.starts_with("'envFiles' must be an array of strings"));
+        assert!(diagnostics[2].2.starts_with("'enabled' must be a boolean"));
+    }
+
+    #[test]
+    fn counts_columns_in_utf16_after_non_ascii_text() {
+        let diagnostics = check("{ \"groups\": { \"ü\": \"Dätä 😀\", \"b\": 1 } }");
+
+        assert_eq!(diagnostics.len(), 1);
+        // The emoji takes two UTF-16 units, each umlaut one.
+        assert_eq!(diagnostics[0].1, (0, 35, 0, 36));
+    }
+
+    #[test]
+    fn ignores_structure_inside_strings() {
+        let content = r#"{ "exclude": ["a\"}{", "[,]:"], "groups": { "x\\": "}" }, "bogus": true }"#;
+        let diagnostics = check(content);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].2, "Unknown configuration key 'bogus'");
+        assert_eq!(diagnostics[0].1, (0, 58, 0, 65));
+    }
+
+    #[test]
+    fn checks_nested_rule_and_requirement_shapes() {
+        let content = r#"{
+  "required": { "A": "yes", "B": ["prod", 1] },
+  "rules": {
+    "oneOf": ["A", ["B", "C"]],
+    "requiresIf": { "MODE": ["A"] },
+    "requires": { "A": "B" }
+  }
+}"#;
+        let lines: Vec<u32> = check(content).into_iter().map(|(_, range, _)| range.0).collect();
+
+        assert_eq!(lines, vec![1, 1, 3, 4, 5]);
+    }
+
+    #[test]
+    fn checks_schema_file_entries() {
+        let content = r#"{ "schemaFiles": ["missing.yml", "Cargo.toml", { "path": "src/main.rs" }, 5] }"#;
+
+        assert_eq!(
+            codes(content),
+            vec!["config-missing-file", "config-unknown-kind", "config-invalid-type", "config-invalid-type"]
+        );
+    }
+
+    #[test]
+    fn completes_missing_top_level_keys() {
+        let items = complete("{\n  \"envFiles\": [\".env\"],\n  ");
+        let labels: Vec<&str> = items.iter().map(|(label, _)| label.as_str()).collect();
+
+        assert!(!labels.contains(&"envFiles"));
+        assert!(labels.contains(&"schemaFiles") && labels.contains(&"rules"));
+        assert!(items.iter().any(|(label, insert)| label == "groups" && insert == "\"groups\": "));
+    }
+
+    #[test]
+    fn treats_aliases_as_seen_keys() {
+        assert!(!labels("{ \"auto_discover\": true, ").contains(&"autoDiscover".to_string()));
+    }
+
+    #[test]
+    fn completes_inside_an_open_key_string() {
+        let items = complete("{ \"disc");
+
+        assert!(items.iter().any(|(label, insert)| label == "discovery" && insert == "discovery"));
+    }
+
+    #[test]
+    fn completes_nested_keys() {
+        assert_eq!(labels("{ \"discovery\": { \"zod\": { "), vec!["enabled", "include", "exclude"]);
+        assert_eq!(labels("{ \"groups\": { \"db\": { \"title\": \"DB\", "), vec!["description"]);
+        assert_eq!(labels("{ \"schemaFiles\": [\"a.yml\", { "), vec!["path", "kind"]);
+        assert_eq!(
+            labels("{ \"rules\": { "),
+            vec!["requires", "requiresIf", "oneOf", "mutuallyExclusive"]
+        );
+        assert!(labels("{ \"profiles\": { ").is_empty());
+        assert!(labels("{ \"required\": { \"A\": [").is_empty());
+    }
+
+    #[test]
+    fn completes_values() {
+        assert_eq!(labels("{ \"autoDiscover\": "), vec!["true", "false"]);
+
+        let kinds = complete("{ \"schemaFiles\": [{ \"path\": \"a.ts\", \"kind\": ");
+        assert_eq!(kinds[0], ("zod".to_string(), "\"zod\"".to_string()));
+        assert_eq!(kinds.len(), 3);
+
+        let in_string = complete("{ \"schemaFiles\": [{ \"kind\": \"py");
+        assert!(in_string.iter().all(|(label, insert)| label == insert));
+    }
+
+    #[test]
+    fn ignores_braces_inside_strings_while_completing() {
+        let items = labels("{ \"exclude\": [\"a\\\"{\", \"[\"],\n  ");
+
+        assert!(items.contains(&"envFiles".to_string()));
+        assert!(!items.contains(&"exclude".to_string()));
+    }
+}
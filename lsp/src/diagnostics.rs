@@ -1,24 +1,42 @@
-use crate::validation::ValidationError;
+use crate::drift::SchemaConflict;
+use crate::secrets::{LeakReason, SecretLeak};
+use crate::types::SchemaLocation;
+use crate::validation::{ValidationError, ValidationErrorKind};
 
 use tower_lsp::lsp_types::{
-    Diagnostic, DiagnosticSeverity, Position, Range, NumberOrString,
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, Location, Position, Range,
+    NumberOrString, Url,
 };
 
-pub fn create_missing_var_diagnostic(error: &ValidationError) -> Diagnostic {
+pub fn create_missing_var_diagnostic(error: &ValidationError, profiles: &[String]) -> Diagnostic {
     let position = Position::new(0, 0);
     let range = Range::new(position, position);
 
-    let mut message = format!(
-        "Missing required environment variable: '{}'",
-        error.var_name
-    );
+    let (code, mut message) = match &error.kind {
+        ValidationErrorKind::NoneOf { vars } => (
+            "env-var-rule",
+            format!("One of {} must be set", quoted_list(vars, "or")),
+        ),
+        _ => (
+            "missing-env-var",
+            format!("Missing required environment variable: '{}'", error.var_name),
+        ),
+    };
 
-    if let Some(description) = &error.schema_var.description {
-        message.push_str(&format!("\n  Description: {}", description));
+    match profiles {
+        [] => {}
+        [profile] => message.push_str(&format!(" (profile '{}')", profile)),
+        _ => message.push_str(&format!(" (profiles: {})", profiles.join(", "))),
     }
 
-    if let Some(default) = &error.schema_var.default {
-        message.push_str(&format!("\n  Default: {}", default));
+    if error.kind == ValidationErrorKind::Missing {
+        if let Some(description) = &error.schema_var.description {
+            message.push_str(&format!("\n  Description: {}", description));
+        }
+
+        if let Some(default) = &error.schema_var.default {
+            message.push_str(&format!("\n  Default: {}", default));
+        }
     }
 
     let severity = Some(DiagnosticSeverity::ERROR);
@@ -26,7 +44,166 @@ pub fn create_missing_var_diagnostic(error: &ValidationError) -> Diagnostic {
     Diagnostic {
         range,
         severity,
-        code: Some(NumberOrString::String("missing-env-var".to_string())),
+        code: Some(NumberOrString::String(code.to_string())),
+        source: Some("env-checker".to_string()),
+        message,
+        related_information: None,
+        tags: None,
+        data: None,
+        code_description: None,
+    }
+}
+
+/// A diagnostic for an error attached to an env entry: an invalid value, or
+/// a rule violation reported on the variable that triggered it.
+pub fn create_entry_diagnostic(error: &ValidationError) -> Diagnostic {
+    match &error.kind {
+        ValidationErrorKind::RequiredBy { .. } | ValidationErrorKind::Conflicting { .. } => {
+            create_rule_diagnostic(error)
+        }
+        _ => create_invalid_value_diagnostic(error),
+    }
+}
+
+pub fn create_rule_diagnostic(error: &ValidationError) -> Diagnostic {
+    let (line, column, name) = error
+        .entry
+        .as_ref()
+        .map_or((0, 0, ""), |e| (e.line, e.column, e.name.as_str()));
+    let range = Range::new(
+        Position::new(line as u32, column as u32),
+        Position::new(line as u32, (column + name.len()) as u32),
+    );
+
+    let message = match &error.kind {
+        ValidationErrorKind::RequiredBy { trigger, value: Some(value) } => format!(
+            "Missing environment variable '{}', required when '{}' is '{}'",
+            error.var_name, trigger, value
+        ),
+        ValidationErrorKind::RequiredBy { trigger, value: None } => format!(
+            "Missing environment variable '{}', required when '{}' is set",
+            error.var_name, trigger
+        ),
+        ValidationErrorKind::Conflicting { others, exactly_one: true } => format!(
+            "'{}' is set together with {}, but only one of them may be set",
+            error.var_name,
+            quoted_list(others, "and")
+        ),
+        ValidationErrorKind::Conflicting { others, exactly_one: false } => format!(
+            "'{}' cannot be set together with {}",
+            error.var_name,
+            quoted_list(others, "and")
+        ),
+        _ => format!("Rule violated by '{}'", error.var_name),
+    };
+
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String("env-var-rule".to_string())),
+        source: Some("env-checker".to_string()),
+        message,
+        related_information: None,
+        tags: None,
+        data: None,
+        code_description: None,
+    }
+}
+
+fn quoted_list(names: &[String], conjunction: &str) -> String {
+    let quoted: Vec<String> = names.iter().map(|n| format!("'{}'", n)).collect();
+    match quoted.as_slice() {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} {} {}", rest.join(", "), conjunction, last),
+    }
+}
+
+pub fn create_invalid_value_diagnostic(error: &ValidationError) -> Diagnostic {
+    let (line, column) = error.entry.as_ref().map_or((0, 0), |e| (e.line, e.column));
+    let range = Range::new(
+        Position::new(line as u32, column as u32),
+        Position::new(line as u32, (column + error.var_name.len()) as u32),
+    );
+
+    let reason = match &error.kind {
+        ValidationErrorKind::InvalidValue { reason, .. } => reason.as_str(),
+        _ => "value is missing",
+    };
+
+    let message = format!(
+        "Invalid value for environment variable '{}': {}",
+        error.var_name, reason
+    );
+
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String("invalid-env-value".to_string())),
+        source: Some("env-checker".to_string()),
+        message,
+        related_information: None,
+        tags: None,
+        data: None,
+        code_description: None,
+    }
+}
+
+pub fn create_deprecated_var_diagnostic(error: &ValidationError) -> Diagnostic {
+    let (line, column) = error.entry.as_ref().map_or((0, 0), |e| (e.line, e.column));
+    let range = Range::new(
+        Position::new(line as u32, column as u32),
+        Position::new(line as u32, (column + error.var_name.len()) as u32),
+    );
+
+    let mut message = format!("Environment variable '{}' is deprecated", error.var_name);
+
+    if let Some(notice) = error.schema_var.deprecated.as_deref().filter(|n| !n.is_empty()) {
+        message.push_str(&format!(": {}", notice));
+    }
+
+    let data = match &error.kind {
+        ValidationErrorKind::Deprecated { replacement: Some(replacement) } => {
+            Some(serde_json::json!({ "replacement": replacement }))
+        }
+        _ => None,
+    };
+
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String("deprecated-env-var".to_string())),
+        source: Some("env-checker".to_string()),
+        message,
+        related_information: None,
+        tags: Some(vec![DiagnosticTag::DEPRECATED]),
+        data,
+        code_description: None,
+    }
+}
+
+pub fn create_secret_leak_diagnostic(leak: &SecretLeak) -> Diagnostic {
+    let entry = &leak.entry;
+    let range = Range::new(
+        Position::new(entry.line as u32, entry.column as u32),
+        Position::new(entry.line as u32, (entry.column + entry.name.len()) as u32),
+    );
+
+    let location = match leak.reason {
+        LeakReason::ExampleFile => "an example file that is meant to be committed",
+        LeakReason::TrackedByGit => "a file tracked by git",
+        LeakReason::NotIgnored => "a file not covered by .gitignore",
+    };
+
+    let message = format!(
+        "Secret '{}' appears to contain a real value in {}. Use a placeholder instead",
+        entry.name, location
+    );
+
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String("secret-leak".to_string())),
         source: Some("env-checker".to_string()),
         message,
         related_information: None,
@@ -36,9 +213,9 @@ pub fn create_missing_var_diagnostic(error: &ValidationError) -> Diagnostic {
     }
 }
 
-pub fn create_unused_var_diagnostic(var_name: &str, line: usize) -> Diagnostic {
-    let position = Position::new(line as u32, 0);
-    let end_position = Position::new(line as u32, var_name.len() as u32);
+pub fn create_unused_var_diagnostic(var_name: &str, line: usize, column: usize) -> Diagnostic {
+    let position = Position::new(line as u32, column as u32);
+    let end_position = Position::new(line as u32, (column + var_name.len()) as u32);
     let range = Range::new(position, end_position);
 
     let message = format!(
@@ -60,3 +237,54 @@ pub fn create_unused_var_diagnostic(var_name: &str, line: usize) -> Diagnostic {
         code_description: None,
     }
 }
+
+/// A warning on one side of a cross-schema conflict, pointing at the other
+/// declaration. `on_second` selects which of the two schema files it is for.
+pub fn create_schema_conflict_diagnostic(conflict: &SchemaConflict, on_second: bool) -> Diagnostic {
+    let (here, there) = if on_second {
+        (conflict.second_location(), conflict.first_location())
+    } else {
+        (conflict.first_location(), conflict.second_location())
+    };
+
+    let related_information = Url::from_file_path(there.source.file_path()).ok().map(|uri| {
+        vec![DiagnosticRelatedInformation {
+            location: Location::new(uri, declaration_range(there, &conflict.name)),
+            message: format!("'{}' is also declared in this {} schema", conflict.name, there.source.kind().name()),
+        }]
+    });
+
+    Diagnostic {
+        range: declaration_range(here, &conflict.name),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String("schema-conflict".to_string())),
+        source: Some("env-checker".to_string()),
+        message: conflict.message(),
+        related_information,
+        tags: None,
+        data: None,
+        code_description: None,
+    }
+}
+
+fn declaration_range(location: &SchemaLocation, name: &str) -> Range {
+    Range::new(
+        Position::new(location.line as u32, location.column as u32),
+        Position::new(location.line as u32, (location.column + name.len()) as u32),
+    )
+}
+
+/// A problem in `.envchecker.json`.
+pub fn create_config_diagnostic(range: Range, severity: DiagnosticSeverity, code: &str, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        code: Some(NumberOrString::String(code.to_string())),
+        source: Some("env-checker".to_string()),
+        message,
+        related_information: None,
+        tags: None,
+        data: None,
+        code_description: None,
+    }
+}
@@ -0,0 +1,53 @@
+use tower_lsp::lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+/// Converts an LSP position (UTF-16 columns) to a byte offset, clamped to the
+/// end of its line.
+pub fn offset_at(text: &str, position: Position) -> usize {
+    let mut offset = 0;
+
+    for (line_number, line) in text.split_inclusive('\n').enumerate() {
+        if line_number as u32 == position.line {
+            let content = line.trim_end_matches(['\n', '\r']);
+            let mut units = 0;
+            for (index, ch) in content.char_indices() {
+                if units >= position.character {
+                    return offset + index;
+                }
+                units += ch.len_utf16() as u32;
+            }
+            return offset + content.len();
+        }
+        offset += line.len();
+    }
+
+    text.len()
+}
+
+/// Converts a byte offset to an LSP position.
+pub fn position_at(text: &str, offset: usize) -> Position {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() as u32;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character = before[line_start..].chars().map(|c| c.len_utf16() as u32).sum();
+
+    Position { line, character }
+}
+
+pub fn range_at(text: &str, start: usize, end: usize) -> Range {
+    Range {
+        start: position_at(text, start),
+        end: position_at(text, end),
+    }
+}
+
+/// Applies a `textDocument/didChange` event to the stored text of a document.
+pub fn apply_change(text: &mut String, change: &TextDocumentContentChangeEvent) {
+    match change.range {
+        Some(range) => {
+            let start = offset_at(text, range.start);
+            let end = offset_at(text, range.end).max(start);
+            text.replace_range(start..end, &change.text);
+        }
+        None => *text = change.text.clone(),
+    }
+}
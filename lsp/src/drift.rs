@@ -0,0 +1,288 @@
+use crate::types::{EnvType, EnvVar, ParsedSchema, SchemaLocation};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    Type,
+    Default,
+    Required,
+}
+
+/// Two schemas declaring the same variable differently.
+///
+/// `first` is the definition that wins when schemas are merged. Both
+/// definitions always carry a location, falling back to the top of their
+/// schema file when the declaration could not be found.
+#[derive(Debug, Clone)]
+pub struct SchemaConflict {
+    pub name: String,
+    pub kind: ConflictKind,
+    pub first: EnvVar,
+    pub second: EnvVar,
+}
+
+impl SchemaConflict {
+    pub fn message(&self) -> String {
+        let first_file = file_name(&self.first);
+        let second_file = file_name(&self.second);
+
+        match self.kind {
+            ConflictKind::Type => format!(
+                "'{}' is declared as {} in {} but as {} in {}",
+                self.name,
+                self.first.var_type.name(),
+                first_file,
+                self.second.var_type.name(),
+                second_file
+            ),
+            ConflictKind::Default => format!(
+                "'{}' defaults to '{}' in {} but to '{}' in {}",
+                self.name,
+                self.first.default.as_deref().unwrap_or_default(),
+                first_file,
+                self.second.default.as_deref().unwrap_or_default(),
+                second_file
+            ),
+            ConflictKind::Required => format!(
+                "'{}' is {} in {} but {} in {}",
+                self.name,
+                requirement(&self.first),
+                first_file,
+                requirement(&self.second),
+                second_file
+            ),
+        }
+    }
+
+    pub fn first_location(&self) -> &SchemaLocation {
+        self.first.location.as_ref().expect("conflicts always carry locations")
+    }
+
+    pub fn second_location(&self) -> &SchemaLocation {
+        self.second.location.as_ref().expect("conflicts always carry locations")
+    }
+}
+
+/// Compares variables declared in more than one schema against the
+/// definition that wins, reporting disagreements on type, default and
+/// whether the variable has to be set.
+///
+/// `integer` and `number` are treated as compatible, as are defaults that
+/// only differ in spelling (`False`/`false`, `3000`/`3000.0`).
+pub fn find_conflicts(schemas: &[ParsedSchema]) -> Vec<SchemaConflict> {
+    let mut winners: HashMap<String, EnvVar> = HashMap::new();
+    let mut conflicts = Vec::new();
+
+    for schema in schemas {
+        for var in &schema.variables {
+            let mut var = var.clone();
+            if var.location.is_none() {
+                var.location = Some(SchemaLocation {
+                    source: schema.source.clone(),
+                    line: 0,
+                    column: 0,
+                });
+            }
+
+            let Some(first) = winners.get(var.name.as_str()) else {
+                winners.insert(var.name.clone(), var);
+                continue;
+            };
+
+            if first.location.as_ref().map(|l| &l.source) == var.location.as_ref().map(|l| &l.source) {
+                continue;
+            }
+
+            let mut kinds = Vec::new();
+            if !compatible_types(&first.var_type, &var.var_type) {
+                kinds.push(ConflictKind::Type);
+            }
+            if let (Some(a), Some(b)) = (&first.default, &var.default) {
+                if !same_default(a, b) {
+                    kinds.push(ConflictKind::Default);
+                }
+            }
+            if is_required(first) != is_required(&var) || required_profiles(first) != required_profiles(&var) {
+                kinds.push(ConflictKind::Required);
+            }
+
+            for kind in kinds {
+                conflicts.push(SchemaConflict {
+                    name: var.name.clone(),
+                    kind,
+                    first: first.clone(),
+                    second: var.clone(),
+                });
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// A variable has to be set when it is neither optional nor has a default.
+fn is_required(var: &EnvVar) -> bool {
+    !var.optional && var.default.is_none()
+}
+
+/// The profiles an otherwise optional variable is required in, sorted.
+fn required_profiles(var: &EnvVar) -> Vec<&str> {
+    if is_required(var) {
+        return Vec::new();
+    }
+    let mut profiles: Vec<&str> = var.required_in.iter().map(String::as_str).collect();
+    profiles.sort_unstable();
+    profiles.dedup();
+    profiles
+}
+
+fn requirement(var: &EnvVar) -> String {
+    let profiles = required_profiles(var);
+    if is_required(var) {
+        "required".to_string()
+    } else if !profiles.is_empty() {
+        format!("required only in {}", profiles.join(", "))
+    } else if var.default.is_some() {
+        "optional (with a default)".to_string()
+    } else {
+        "optional".to_string()
+    }
+}
+
+fn compatible_types(a: &EnvType, b: &EnvType) -> bool {
+    let numeric = |t: &EnvType| matches!(t, EnvType::Integer | EnvType::Number);
+    a == b || (numeric(a) && numeric(b))
+}
+
+fn same_default(a: &str, b: &str) -> bool {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x == y,
+        _ => {
+            let is_bool = a.eq_ignore_ascii_case("true") || a.eq_ignore_ascii_case("false");
+            a == b || (is_bool && a.eq_ignore_ascii_case(b))
+        }
+    }
+}
+
+fn file_name(var: &EnvVar) -> String {
+    var.location
+        .as_ref()
+        .and_then(|l| Path::new(l.source.file_path()).file_name())
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Rules, SchemaSource};
+
+    fn var(name: &str, var_type: EnvType) -> EnvVar {
+        EnvVar { name: name.to_string(), var_type, ..Default::default() }
+    }
+
+    fn schema(file: &str, variables: Vec<EnvVar>) -> ParsedSchema {
+        let file_path = format!("/repo/{}", file);
+        let source = match file.rsplit('.').next() {
+            Some("ts") => SchemaSource::Zod { file_path },
+            Some("py") => SchemaSource::Pydantic { file_path },
+            _ => SchemaSource::Yaml { file_path },
+        };
+        ParsedSchema { source, variables, rules: Rules::default() }
+    }
+
+    fn kinds(conflicts: &[SchemaConflict]) -> Vec<(&str, ConflictKind)> {
+        conflicts.iter().map(|c| (c.name.as_str(), c.kind)).collect()
+    }
+
+    #[test]
+    fn reports_type_disagreements_with_both_files() {
+        let conflicts = find_conflicts(&[
+            schema("env.ts", vec![var("PORT", EnvType::Integer)]),
+            schema("settings.py", vec![var("PORT", EnvType::String)]),
+        ]);
+
+        assert_eq!(kinds(&conflicts), [("PORT", ConflictKind::Type)]);
+        assert_eq!(conflicts[0].message(), "'PORT' is declared as integer in env.ts but as string in settings.py");
+        assert_eq!(conflicts[0].first_location().source.file_path(), "/repo/env.ts");
+        assert_eq!(conflicts[0].second_location().source.file_path(), "/repo/settings.py");
+        assert_eq!(conflicts[0].second_location().line, 0);
+    }
+
+    #[test]
+    fn treats_integer_and_number_and_default_spellings_as_equal() {
+        let conflicts = find_conflicts(&[
+            schema(
+                "env.ts",
+                vec![
+                    EnvVar { default: Some("3000".to_string()), ..var("PORT", EnvType::Number) },
+                    EnvVar { default: Some("false".to_string()), ..var("DEBUG", EnvType::Boolean) },
+                ],
+            ),
+            schema(
+                "settings.py",
+                vec![
+                    EnvVar { default: Some("3000.0".to_string()), ..var("PORT", EnvType::Integer) },
+                    EnvVar { default: Some("False".to_string()), ..var("DEBUG", EnvType::Boolean) },
+                ],
+            ),
+        ]);
+
+        assert!(conflicts.is_empty(), "{:?}", kinds(&conflicts));
+    }
+
+    #[test]
+    fn reports_default_and_requirement_disagreements() {
+        let conflicts = find_conflicts(&[
+            schema(
+                "env.schema.yml",
+                vec![
+                    EnvVar { default: Some("info".to_string()), optional: true, ..var("LOG_LEVEL", EnvType::String) },
+                    var("API_KEY", EnvType::String),
+                    EnvVar {
+                        optional: true,
+                        required_in: vec!["production".to_string()],
+                        ..var("SENTRY_DSN", EnvType::String)
+                    },
+                ],
+            ),
+            schema(
+                "env.ts",
+                vec![
+                    EnvVar { default: Some("debug".to_string()), optional: true, ..var("LOG_LEVEL", EnvType::String) },
+                    EnvVar { optional: true, ..var("API_KEY", EnvType::String) },
+                    EnvVar { optional: true, ..var("SENTRY_DSN", EnvType::String) },
+                ],
+            ),
+        ]);
+
+        assert_eq!(
+            kinds(&conflicts),
+            [
+                ("LOG_LEVEL", ConflictKind::Default),
+                ("API_KEY", ConflictKind::Required),
+                ("SENTRY_DSN", ConflictKind::Required),
+            ]
+        );
+        assert_eq!(conflicts[0].message(), "'LOG_LEVEL' defaults to 'info' in env.schema.yml but to 'debug' in env.ts");
+        assert_eq!(conflicts[1].message(), "'API_KEY' is required in env.schema.yml but optional in env.ts");
+        assert_eq!(
+            conflicts[2].message(),
+            "'SENTRY_DSN' is required only in production in env.schema.yml but optional in env.ts"
+        );
+    }
+
+    #[test]
+    fn compares_later_schemas_against_the_first_definition() {
+        let conflicts = find_conflicts(&[
+            schema("env.schema.yml", vec![var("PORT", EnvType::Integer), var("PORT", EnvType::String)]),
+            schema("env.ts", vec![var("PORT", EnvType::Integer)]),
+            schema("settings.py", vec![var("PORT", EnvType::Boolean)]),
+        ]);
+
+        assert_eq!(kinds(&conflicts), [("PORT", ConflictKind::Type)]);
+        assert_eq!(conflicts[0].first.location.as_ref().unwrap().source.file_path(), "/repo/env.schema.yml");
+        assert_eq!(conflicts[0].second_location().source.file_path(), "/repo/settings.py");
+    }
+}
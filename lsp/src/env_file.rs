@@ -4,26 +4,41 @@ use regex::Regex;
 use std::fs;
 use std::path::Path;
 
+/// Returns true for `.env`, `.env.*` and `*.env` files.
+pub fn is_env_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+
+    name == ".env" || name.starts_with(".env.") || name.ends_with(".env")
+}
+
 pub fn parse_env_file(file_path: &Path) -> Result<Vec<EnvFileEntry>> {
     let content = fs::read_to_string(file_path)
         .context(format!("Failed to read env file: {}", file_path.display()))?;
 
+    Ok(parse_env_content(&content, file_path))
+}
+
+/// Parses the text of an env file, such as an unsaved editor buffer.
+pub fn parse_env_content(content: &str, file_path: &Path) -> Vec<EnvFileEntry> {
     let mut entries = Vec::new();
-    let comment_regex = Regex::new(r"^\s*#.*$")?;
-    let empty_regex = Regex::new(r"^\s*$")?;
-    let export_regex = Regex::new(r"^\s*export\s+")?;
-    let var_regex = Regex::new(r"^([A-Za-z_][A-Za-z0-9_]*)\s*=\s*(.*)$")?;
+    let comment_regex = Regex::new(r"^\s*#.*$").unwrap();
+    let empty_regex = Regex::new(r"^\s*$").unwrap();
+    let export_regex = Regex::new(r"^\s*export\s+").unwrap();
+    let var_regex = Regex::new(r"^([A-Za-z_][A-Za-z0-9_]*)\s*=\s*(.*)$").unwrap();
 
-    for (line_num, line) in content.lines().enumerate() {
-        let line = line.trim();
+    for (line_num, raw_line) in content.lines().enumerate() {
+        let line = raw_line.trim();
 
         if comment_regex.is_match(line) || empty_regex.is_match(line) {
             continue;
         }
 
         let line = export_regex.replace(line, "");
+        let column = raw_line.len() - line.len() - (raw_line.len() - raw_line.trim_end().len());
 
-        if let Some(captures) = var_regex.captures(&*line) {
+        if let Some(captures) = var_regex.captures(&line) {
             let name = captures.get(1).unwrap().as_str().to_string();
             let raw_value = captures.get(2).unwrap().as_str().trim();
 
@@ -37,15 +52,16 @@ pub fn parse_env_file(file_path: &Path) -> Result<Vec<EnvFileEntry>> {
                 name,
                 value,
                 line: line_num,
+                column,
                 file_path: file_path.to_string_lossy().to_string(),
             });
         }
     }
 
-    Ok(entries)
+    entries
 }
 
-fn parse_env_value(value: &str) -> String {
+pub fn parse_env_value(value: &str) -> String {
     let value = value.trim();
 
     if (value.starts_with('"') && value.ends_with('"')) 
@@ -55,10 +71,9 @@ fn parse_env_value(value: &str) -> String {
         stripped.replace(r"\n", "\n").replace(r"\t", "\t").replace(r"\\", "\\")
     } else if value.starts_with('"') && !value.ends_with('"') {
         let mut result = String::new();
-        let mut chars = value[1..].chars().peekable();
         let mut in_escape = false;
 
-        while let Some(c) = chars.next() {
+        for c in value[1..].chars() {
             if in_escape {
                 match c {
                     'n' => result.push('\n'),
@@ -95,3 +110,83 @@ pub fn merge_env_files(entries_list: Vec<Vec<EnvFileEntry>>) -> Vec<EnvFileEntry
 
     merged
 }
+
+/// Parses a stack of env files where later files override earlier ones,
+/// e.g. `[.env, .env.production]`. Files that cannot be read are skipped.
+pub fn load_layered_env_files(paths: &[String]) -> Vec<EnvFileEntry> {
+    let layers = paths
+        .iter()
+        .rev()
+        .filter_map(|path| parse_env_file(Path::new(path)).ok())
+        .collect();
+
+    merge_env_files(layers)
+}
+
+/// Builds entries from `(name, value)` pairs, e.g. `std::env::vars()`.
+///
+/// `source` is used as the entry's file path, since the variables do not
+/// come from a file. Variables with empty values are left out, so they are
+/// reported as missing just like unset ones.
+pub fn entries_from_vars(vars: impl IntoIterator<Item = (String, String)>, source: &str) -> Vec<EnvFileEntry> {
+    let mut entries: Vec<_> = vars
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| EnvFileEntry {
+            name,
+            value: Some(value),
+            line: 0,
+            column: 0,
+            file_path: source.to_string(),
+        })
+        .collect();
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+}
+
+/// The file path of entries read from the environment of the current process.
+pub const PROCESS_ENV_SOURCE: &str = "<environment>";
+
+/// Builds entries from the environment of the current process.
+pub fn entries_from_process_env() -> Vec<EnvFileEntry> {
+    let vars = std::env::vars_os().map(|(name, value)| {
+        (name.to_string_lossy().to_string(), value.to_string_lossy().to_string())
+    });
+
+    entries_from_vars(vars, PROCESS_ENV_SOURCE)
+}
+
+/// Reads an environment dump in JSON, either an object mapping names to
+/// values or an array of `NAME=value` strings (as in `docker inspect`).
+/// `null` and empty values count as unset.
+pub fn parse_env_json(file_path: &Path) -> Result<Vec<EnvFileEntry>> {
+    let content = fs::read_to_string(file_path)
+        .context(format!("Failed to read environment dump: {}", file_path.display()))?;
+
+    let json: serde_json::Value = serde_json::from_str(&content)
+        .context(format!("Failed to parse environment dump: {}", file_path.display()))?;
+
+    let vars: Vec<(String, String)> = match json {
+        serde_json::Value::Object(map) => map
+            .into_iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::String(s) => s,
+                    serde_json::Value::Null => String::new(),
+                    other => other.to_string(),
+                };
+                (name, value)
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter_map(|item| item.as_str())
+            .filter_map(|item| item.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect(),
+        _ => anyhow::bail!("Environment dump must be a JSON object or an array of NAME=value strings"),
+    };
+
+    Ok(entries_from_vars(vars, &file_path.to_string_lossy()))
+}
@@ -0,0 +1,210 @@
+use crate::types::{EnvVar, Groups, VariableGroup};
+use crate::validation::group_variables;
+use regex::Regex;
+use std::collections::HashSet;
+
+pub fn generate_example_content(vars: &[EnvVar], groups: &Groups) -> String {
+    let grouped = group_variables(vars, groups);
+    let mut content = String::new();
+
+    for group in grouped {
+        content.push_str(&format_group_header(&group));
+
+        for var in &group.vars {
+            content.push_str(&format_var_entry(var, true));
+        }
+
+        content.push('\n');
+    }
+
+    content.trim_end_matches('\n').to_string()
+}
+
+/// The comment lines opening a group's section: its title, followed by its
+/// description when one is configured.
+pub fn format_group_header(group: &VariableGroup) -> String {
+    let mut header = format!("# {}\n", group.title);
+
+    for line in group.description.iter().flat_map(|d| d.lines()) {
+        header.push_str(&format!("# {}\n", line));
+    }
+
+    header
+}
+
+pub fn format_var_entry(var: &EnvVar, is_example: bool) -> String {
+    let mut result = String::new();
+
+    if let Some(desc) = &var.description {
+        result.push_str(&format!("# {}\n", desc));
+    }
+
+    if let Some(deprecated) = &var.deprecated {
+        if deprecated.is_empty() {
+            result.push_str("# Deprecated\n");
+        } else {
+            result.push_str(&format!("# Deprecated: {}\n", deprecated));
+        }
+    }
+
+    if var.optional {
+        result.push('#');
+    }
+
+    let example = if is_example && !var.secret { var.example.as_deref() } else { None };
+    let default_value = var.default.as_deref().or(example).unwrap_or("");
+    result.push_str(&format!("{}={}\n", var.name, default_value));
+
+    result
+}
+
+/// Brings an existing `.env.example` in line with the schema while keeping
+/// what people wrote by hand.
+///
+/// Lines of variables that are still declared are kept verbatim, including
+/// their values, comments and order. Variables that are no longer declared
+/// are removed together with the comment lines directly above them, while
+/// commented-out assignments such as `# FOO=` are left alone. New variables
+/// are added to the end of their group's section, or to a new section at the
+/// end of the file.
+pub fn update_example_content(existing: &str, vars: &[EnvVar], groups: &Groups) -> String {
+    let var_regex = Regex::new(r"^\s*(?:export\s+)?([A-Za-z_][A-Za-z0-9_]*)\s*=").unwrap();
+    let commented_regex = Regex::new(r"^\s*#?\s*(?:export\s+)?([A-Za-z_][A-Za-z0-9_]*)\s*=").unwrap();
+    let var_name = |line: &str| var_regex.captures(line).map(|c| c[1].to_string());
+    let mentioned_name = |line: &str| commented_regex.captures(line).map(|c| c[1].to_string());
+
+    let declared: HashSet<&str> = vars.iter().map(|v| v.name.as_str()).collect();
+    let headers: HashSet<String> = group_variables(vars, groups)
+        .iter()
+        .flat_map(|group| format_group_header(group).lines().map(String::from).collect::<Vec<_>>())
+        .collect();
+
+    let lines: Vec<&str> = existing.lines().collect();
+    let mut keep = vec![true; lines.len()];
+
+    for (i, line) in lines.iter().enumerate() {
+        let Some(name) = var_name(line) else { continue };
+        if declared.contains(name.as_str()) {
+            continue;
+        }
+
+        keep[i] = false;
+        for j in (0..i).rev() {
+            let previous = lines[j].trim();
+            if !previous.starts_with('#') || mentioned_name(previous).is_some() || headers.contains(previous) {
+                break;
+            }
+            keep[j] = false;
+        }
+    }
+
+    // Drop the blank lines left behind by removed entries.
+    let mut result: Vec<String> = Vec::new();
+    for (line, _) in lines.iter().zip(&keep).filter(|(_, keep)| **keep) {
+        if line.trim().is_empty() && result.last().is_none_or(|l| l.trim().is_empty()) {
+            continue;
+        }
+        result.push(line.to_string());
+    }
+
+    let present: HashSet<String> = result.iter().filter_map(|l| mentioned_name(l)).collect();
+    let missing: Vec<EnvVar> = vars.iter().filter(|v| !present.contains(&v.name)).cloned().collect();
+
+    for group in group_variables(&missing, groups) {
+        let entries: Vec<String> = group
+            .vars
+            .iter()
+            .flat_map(|var| format_var_entry(var, true).lines().map(String::from).collect::<Vec<_>>())
+            .collect();
+
+        let header = format!("# {}", group.title);
+        match result.iter().position(|l| l.trim() == header) {
+            Some(start) => {
+                let end = result[start + 1..]
+                    .iter()
+                    .position(|l| l.trim().is_empty())
+                    .map_or(result.len(), |offset| start + 1 + offset);
+                result.splice(end..end, entries);
+            }
+            None => {
+                while result.last().is_some_and(|l| l.trim().is_empty()) {
+                    result.pop();
+                }
+                if !result.is_empty() {
+                    result.push(String::new());
+                }
+                result.extend(format_group_header(&group).lines().map(String::from));
+                result.extend(entries);
+            }
+        }
+    }
+
+    while result.last().is_some_and(|l| l.trim().is_empty()) {
+        result.pop();
+    }
+
+    let mut content = result.join("\n");
+    content.push('\n');
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::EnvType;
+
+    fn var(name: &str, group: Option<&str>) -> EnvVar {
+        EnvVar {
+            name: name.to_string(),
+            var_type: EnvType::String,
+            group: group.map(String::from),
+            ..Default::default()
+        }
+    }
+
+    fn schema() -> Vec<EnvVar> {
+        vec![
+            EnvVar { description: Some("Primary database".to_string()), ..var("DATABASE_URL", Some("Database")) },
+            EnvVar { default: Some("5".to_string()), ..var("DATABASE_POOL", Some("Database")) },
+            EnvVar { optional: true, ..var("LOG_LEVEL", None) },
+        ]
+    }
+
+    #[test]
+    fn regenerating_is_stable() {
+        let generated = generate_example_content(&schema(), &Groups::new());
+        let updated = update_example_content(&generated, &schema(), &Groups::new());
+
+        assert_eq!(updated, format!("{}\n", generated));
+        assert_eq!(update_example_content(&updated, &schema(), &Groups::new()), updated);
+    }
+
+    #[test]
+    fn keeps_hand_written_values_comments_and_order() {
+        let existing = "# Ask the ops team for credentials\nDATABASE_POOL=10\n\n# Database\n# Primary database, see the wiki\nDATABASE_URL=postgres://localhost/dev\n\n# Other\n#LOG_LEVEL=debug\n";
+
+        assert_eq!(update_example_content(existing, &schema(), &Groups::new()), existing);
+    }
+
+    #[test]
+    fn removes_undeclared_variables_with_their_comments() {
+        let existing = "# Database\n# Primary database\nDATABASE_URL=\nDATABASE_POOL=5\n\n# Legacy cache\n# Host of the old cache\nCACHE_HOST=localhost\n# FEATURE_FLAG=on\n\n# Other\n#LOG_LEVEL=\n";
+
+        assert_eq!(
+            update_example_content(existing, &schema(), &Groups::new()),
+            "# Database\n# Primary database\nDATABASE_URL=\nDATABASE_POOL=5\n\n# FEATURE_FLAG=on\n\n# Other\n#LOG_LEVEL=\n"
+        );
+    }
+
+    #[test]
+    fn adds_new_variables_to_their_group_or_a_new_section() {
+        let existing = "# Database\nDATABASE_URL=postgres://localhost/dev\n\n# Notes\n# Keep secrets out of this file\n";
+        let mut vars = schema();
+        vars.push(EnvVar { description: Some("Queue URL".to_string()), ..var("QUEUE_URL", Some("Queue")) });
+
+        assert_eq!(
+            update_example_content(existing, &vars, &Groups::new()),
+            "# Database\nDATABASE_URL=postgres://localhost/dev\nDATABASE_POOL=5\n\n# Notes\n# Keep secrets out of this file\n\n# Other\n#LOG_LEVEL=\n\n# Queue\n# Queue URL\nQUEUE_URL=\n"
+        );
+    }
+}
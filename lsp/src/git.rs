@@ -0,0 +1,505 @@
+use anyhow::{bail, Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Walks up from `path` to the first directory containing a `.git`
+/// directory, or a `.git` file pointing to one as in worktrees and
+/// submodules.
+pub fn find_repo_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| git_dir(dir).is_some())
+        .map(Path::to_path_buf)
+}
+
+/// The git directory of the working tree at `repo_root`: `.git` itself, or
+/// the directory named by the `gitdir:` line of a `.git` file.
+pub fn git_dir(repo_root: &Path) -> Option<PathBuf> {
+    let dot_git = repo_root.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+
+    let content = fs::read_to_string(&dot_git).ok()?;
+    let target = content.lines().find_map(|line| line.strip_prefix("gitdir:"))?.trim();
+    let dir = repo_root.join(target);
+    dir.is_dir().then_some(dir)
+}
+
+/// The directory holding what all worktrees of a repository share, such as
+/// `info/exclude`. Linked worktrees name it in their `commondir` file.
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common) => git_dir.join(common.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+type IndexCache = HashMap<PathBuf, (SystemTime, u64, Arc<HashSet<PathBuf>>)>;
+
+fn index_cache() -> &'static Mutex<IndexCache> {
+    static CACHE: OnceLock<Mutex<IndexCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Reads the paths of all files staged in the git index.
+///
+/// Supports index versions 2 to 4. Returned paths are absolute, joined onto
+/// `repo_root`. The parsed index is cached until the file's modification
+/// time or size changes.
+pub fn tracked_files(repo_root: &Path) -> Result<Arc<HashSet<PathBuf>>> {
+    let git_dir = git_dir(repo_root).context(format!("Not a git repository: {}", repo_root.display()))?;
+    let index_path = git_dir.join("index");
+    let metadata = fs::metadata(&index_path)
+        .context(format!("Failed to read git index: {}", index_path.display()))?;
+    let modified = metadata.modified()?;
+
+    if let Ok(cache) = index_cache().lock() {
+        if let Some((mtime, len, files)) = cache.get(&index_path) {
+            if *mtime == modified && *len == metadata.len() {
+                return Ok(files.clone());
+            }
+        }
+    }
+
+    let files = Arc::new(read_index(repo_root, &index_path)?);
+    if let Ok(mut cache) = index_cache().lock() {
+        cache.insert(index_path, (modified, metadata.len(), files.clone()));
+    }
+    Ok(files)
+}
+
+fn read_index(repo_root: &Path, index_path: &Path) -> Result<HashSet<PathBuf>> {
+    let data = fs::read(index_path)
+        .context(format!("Failed to read git index: {}", index_path.display()))?;
+
+    if data.len() < 12 || &data[0..4] != b"DIRC" {
+        bail!("Invalid git index signature");
+    }
+
+    let version = read_u32(&data, 4)?;
+    if !(2..=4).contains(&version) {
+        bail!("Unsupported git index version {}", version);
+    }
+
+    let count = read_u32(&data, 8)? as usize;
+    let mut files = HashSet::with_capacity(count);
+    let mut offset = 12;
+    let mut previous = Vec::new();
+
+    for _ in 0..count {
+        let entry_start = offset;
+        // ctime, mtime, dev, ino, mode, uid, gid, size and the object id
+        offset += 40 + 20;
+        let flags = read_u16(&data, offset)?;
+        offset += 2;
+
+        if version >= 3 && flags & 0x4000 != 0 {
+            offset += 2;
+        }
+
+        let path = if version == 4 {
+            let (strip, len) = read_varint(&data, offset)?;
+            offset += len;
+            let end = find_nul(&data, offset)?;
+            let keep = previous.len().checked_sub(strip).context("Corrupt git index path")?;
+            let mut path = previous[..keep].to_vec();
+            path.extend_from_slice(&data[offset..end]);
+            offset = end + 1;
+            path
+        } else {
+            let end = find_nul(&data, offset)?;
+            let path = data[offset..end].to_vec();
+            // Entries are NUL-padded to a multiple of eight bytes.
+            let entry_len = end - entry_start + 1;
+            offset = entry_start + entry_len.div_ceil(8) * 8;
+            path
+        };
+
+        files.insert(repo_root.join(String::from_utf8_lossy(&path).as_ref()));
+        previous = path;
+    }
+
+    Ok(files)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data.get(offset..offset + 4).context("Truncated git index")?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = data.get(offset..offset + 2).context("Truncated git index")?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_varint(data: &[u8], offset: usize) -> Result<(usize, usize)> {
+    let mut len = 0;
+    let mut byte = *data.get(offset).context("Truncated git index")?;
+    let mut value = (byte & 0x7f) as usize;
+    len += 1;
+
+    while byte & 0x80 != 0 {
+        byte = *data.get(offset + len).context("Truncated git index")?;
+        value = ((value + 1) << 7) | (byte & 0x7f) as usize;
+        len += 1;
+    }
+
+    Ok((value, len))
+}
+
+fn find_nul(data: &[u8], offset: usize) -> Result<usize> {
+    data.get(offset..)
+        .and_then(|rest| rest.iter().position(|b| *b == 0))
+        .map(|pos| offset + pos)
+        .context("Truncated git index path")
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    base: PathBuf,
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+/// A set of `.gitignore` rules. Later rules take precedence over earlier ones.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads `.git/info/exclude` and every `.gitignore` between `repo_root`
+    /// and `dir`, in the order git applies them.
+    pub fn for_directory(repo_root: &Path, dir: &Path) -> Self {
+        let mut rules = Self::new();
+        if let Some(git_dir) = git_dir(repo_root) {
+            rules.add_file(&common_dir(&git_dir).join("info").join("exclude"), repo_root);
+        }
+
+        let mut dirs: Vec<_> = dir.ancestors().take_while(|d| d.starts_with(repo_root)).collect();
+        dirs.reverse();
+        for d in dirs {
+            rules.add_file(&d.join(".gitignore"), d);
+        }
+
+        rules
+    }
+
+    /// Adds the rules of an ignore file, matched relative to `base`.
+    /// Missing files are ignored.
+    pub fn add_file(&mut self, path: &Path, base: &Path) {
+        if let Ok(content) = fs::read_to_string(path) {
+            for line in content.lines() {
+                self.add_line(line, base);
+            }
+        }
+    }
+
+    pub fn add_line(&mut self, line: &str, base: &Path) {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.trim_start_matches('/');
+
+        if let Ok(pattern) = Pattern::new(line) {
+            self.rules.push(IgnoreRule {
+                base: base.to_path_buf(),
+                pattern,
+                negated,
+                dir_only,
+                anchored,
+            });
+        }
+    }
+
+    /// Checks a single path against the rules, without looking at its parents.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut ignored = false;
+
+        for rule in &self.rules {
+            if rule.dir_only && !is_dir {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&rule.base) else {
+                continue;
+            };
+
+            let matched = if rule.anchored {
+                rule.pattern.matches_path_with(relative, MATCH_OPTIONS)
+            } else {
+                path.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| rule.pattern.matches_with(n, MATCH_OPTIONS))
+            };
+
+            if matched {
+                ignored = !rule.negated;
+            }
+        }
+
+        ignored
+    }
+}
+
+/// Returns true when git would ignore `path`, either directly or because one
+/// of its parent directories is ignored.
+pub fn is_ignored(repo_root: &Path, path: &Path) -> bool {
+    let Some(parent) = path.parent() else {
+        return false;
+    };
+    let rules = IgnoreRules::for_directory(repo_root, parent);
+
+    let parent_ignored = parent
+        .ancestors()
+        .take_while(|d| d.starts_with(repo_root) && *d != repo_root)
+        .any(|d| rules.is_ignored(d, true));
+
+    parent_ignored || rules.is_ignored(path, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    /// Git's offset encoding of the prefix length in version 4 entries.
+    fn encode_varint(mut value: usize) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7f) as u8];
+        while value >> 7 != 0 {
+            value = (value >> 7) - 1;
+            bytes.push(0x80 | (value & 0x7f) as u8);
+        }
+        bytes.reverse();
+        bytes
+    }
+
+    /// Builds an index file holding `paths`, each with extended flags when
+    /// its flag is set (only valid from version 3 on).
+    fn build_index(version: u32, paths: &[(&str, bool)]) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&version.to_be_bytes());
+        data.extend_from_slice(&(paths.len() as u32).to_be_bytes());
+
+        let mut previous = "";
+        for (path, extended) in paths {
+            let entry_start = data.len();
+            data.extend_from_slice(&[0; 60]);
+            let flags = (path.len().min(0xfff) as u16) | if *extended { 0x4000 } else { 0 };
+            data.extend_from_slice(&flags.to_be_bytes());
+            if *extended {
+                data.extend_from_slice(&[0, 0]);
+            }
+
+            if version == 4 {
+                let common = previous.bytes().zip(path.bytes()).take_while(|(a, b)| a == b).count();
+                data.extend(encode_varint(previous.len() - common));
+                data.extend_from_slice(&path.as_bytes()[common..]);
+                data.push(0);
+            } else {
+                data.extend_from_slice(path.as_bytes());
+                let entry_len = data.len() - entry_start + 1;
+                data.resize(entry_start + entry_len.div_ceil(8) * 8, 0);
+            }
+            previous = path;
+        }
+
+        // Extensions and the trailing checksum are never read.
+        data.extend_from_slice(&[0; 20]);
+        data
+    }
+
+    fn read_test_index(name: &str, data: &[u8]) -> Result<HashSet<PathBuf>> {
+        let dir = TempDir::new(&format!("git-index-{}", name));
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join(".git").join("index"), data).unwrap();
+        tracked_files(dir.path()).map(|files| {
+            files.iter().map(|f| f.strip_prefix(dir.path()).unwrap().to_path_buf()).collect()
+        })
+    }
+
+    fn paths(names: &[&str]) -> HashSet<PathBuf> {
+        names.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn reads_version_2_entries() {
+        // Path lengths around the eight-byte padding boundary.
+        let names = [".env", "a/.env.example", "apps/web/.env.production", "abcdefg"];
+        let entries: Vec<_> = names.iter().map(|n| (*n, false)).collect();
+
+        let files = read_test_index("v2", &build_index(2, &entries)).unwrap();
+        assert_eq!(files, paths(&names));
+    }
+
+    #[test]
+    fn reads_version_3_extended_flags() {
+        let entries = [(".env", false), ("config/.env.local", true), ("z.env", false)];
+
+        let files = read_test_index("v3", &build_index(3, &entries)).unwrap();
+        assert_eq!(files, paths(&[".env", "config/.env.local", "z.env"]));
+    }
+
+    #[test]
+    fn reads_version_4_prefix_compressed_paths() {
+        let long = format!("{}/.env", "nested/".repeat(30));
+        let names = ["apps/api/.env", "apps/api/.env.example", "apps/web/.env", long.as_str(), "b.env"];
+        let entries: Vec<_> = names.iter().map(|n| (*n, false)).collect();
+
+        let files = read_test_index("v4", &build_index(4, &entries)).unwrap();
+        assert_eq!(files, paths(&names));
+    }
+
+    #[test]
+    fn reads_multi_byte_varints() {
+        assert_eq!(read_varint(&[0x05], 0).unwrap(), (5, 1));
+        for value in [127, 128, 300, 16511, 16512] {
+            let bytes = encode_varint(value);
+            assert_eq!(read_varint(&bytes, 0).unwrap(), (value, bytes.len()), "value {}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_indexes() {
+        let mut bad_signature = build_index(2, &[(".env", false)]);
+        bad_signature[0] = b'X';
+        assert!(read_test_index("signature", &bad_signature).is_err());
+
+        assert!(read_test_index("version", &build_index(5, &[])).is_err());
+
+        let truncated = build_index(2, &[("apps/web/.env", false)]);
+        assert!(read_test_index("truncated", &truncated[..30]).is_err());
+
+        // A version 4 entry cannot strip more than the previous path.
+        let mut corrupt = build_index(4, &[]);
+        corrupt.truncate(12);
+        corrupt[11] = 1;
+        corrupt.extend_from_slice(&[0; 62]);
+        corrupt.extend(encode_varint(3));
+        corrupt.extend_from_slice(b".env\0");
+        assert!(read_test_index("strip", &corrupt).is_err());
+    }
+
+    #[test]
+    fn rereads_the_index_when_it_changes() {
+        let dir = TempDir::new("git-index-cache");
+        let index = dir.path().join(".git").join("index");
+        fs::create_dir_all(index.parent().unwrap()).unwrap();
+
+        fs::write(&index, build_index(2, &[(".env", false)])).unwrap();
+        assert_eq!(tracked_files(dir.path()).unwrap().len(), 1);
+
+        fs::write(&index, build_index(2, &[(".env", false), (".env.local", false)])).unwrap();
+        assert_eq!(tracked_files(dir.path()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn follows_git_files_of_worktrees() {
+        let dir = TempDir::new("git-worktree");
+        let main_git = dir.path().join("main").join(".git");
+        let worktree_git = main_git.join("worktrees").join("feature");
+        fs::create_dir_all(&worktree_git).unwrap();
+        fs::write(worktree_git.join("commondir"), "../..\n").unwrap();
+        fs::write(worktree_git.join("index"), build_index(2, &[(".env", false)])).unwrap();
+        dir.write("main/.git/info/exclude", "*.secret\n");
+        let worktree = dir.path().join("feature");
+        dir.write("feature/.git", "gitdir: ../main/.git/worktrees/feature\n");
+
+        assert_eq!(find_repo_root(&worktree.join("app").join(".env")), Some(worktree.clone()));
+        assert!(tracked_files(&worktree).unwrap().contains(&worktree.join(".env")));
+        assert!(is_ignored(&worktree, &worktree.join("key.secret")));
+    }
+
+    fn rules(lines: &[&str]) -> IgnoreRules {
+        let mut rules = IgnoreRules::new();
+        for line in lines {
+            rules.add_line(line, Path::new("/repo"));
+        }
+        rules
+    }
+
+    #[test]
+    fn negation_re_includes_later_matches() {
+        let rules = rules(&[".env*", "!.env.example", "# comment", ""]);
+
+        assert!(rules.is_ignored(Path::new("/repo/.env"), false));
+        assert!(rules.is_ignored(Path::new("/repo/apps/.env.local"), false));
+        assert!(!rules.is_ignored(Path::new("/repo/.env.example"), false));
+        assert!(!rules.is_ignored(Path::new("/repo/apps/.env.example"), false));
+    }
+
+    #[test]
+    fn later_rules_take_precedence() {
+        let rules = rules(&["!.env.example", ".env*"]);
+
+        assert!(rules.is_ignored(Path::new("/repo/.env.example"), false));
+    }
+
+    #[test]
+    fn anchored_patterns_only_match_relative_to_their_base() {
+        let rules = rules(&["/build", "config/*.env"]);
+
+        assert!(rules.is_ignored(Path::new("/repo/build"), true));
+        assert!(!rules.is_ignored(Path::new("/repo/apps/build"), true));
+        assert!(rules.is_ignored(Path::new("/repo/config/local.env"), false));
+        assert!(!rules.is_ignored(Path::new("/repo/apps/config/local.env"), false));
+        assert!(!rules.is_ignored(Path::new("/repo/config/nested/local.env"), false));
+    }
+
+    #[test]
+    fn unanchored_patterns_match_file_names_at_any_depth() {
+        let rules = rules(&["*.log", "secrets.env"]);
+
+        assert!(rules.is_ignored(Path::new("/repo/a/b/debug.log"), false));
+        assert!(rules.is_ignored(Path::new("/repo/deep/secrets.env"), false));
+        assert!(!rules.is_ignored(Path::new("/elsewhere/secrets.env"), false));
+    }
+
+    #[test]
+    fn directory_patterns_skip_files() {
+        let rules = rules(&["logs/"]);
+
+        assert!(rules.is_ignored(Path::new("/repo/logs"), true));
+        assert!(rules.is_ignored(Path::new("/repo/app/logs"), true));
+        assert!(!rules.is_ignored(Path::new("/repo/logs"), false));
+    }
+
+    #[test]
+    fn files_in_ignored_directories_stay_ignored() {
+        let dir = TempDir::new("git-parents");
+        dir.write(".gitignore", "build/\n!*.env\n");
+        dir.write("app/.gitignore", "*.secret\n");
+        let root = dir.path();
+
+        assert!(is_ignored(root, &root.join("build").join("prod.env")));
+        assert!(is_ignored(root, &root.join("app").join("key.secret")));
+        assert!(!is_ignored(root, &root.join("app").join(".env")));
+        assert!(!is_ignored(root, &root.join("key.secret")));
+    }
+}
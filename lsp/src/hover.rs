@@ -1,30 +1,78 @@
-use crate::types::EnvVar;
+use crate::secrets::mask_value;
+use crate::types::{EnvVar, Groups};
 
 use tower_lsp::lsp_types::{
     Hover, HoverContents, MarkupContent, MarkupKind,
 };
 
-pub fn create_hover(var: &EnvVar) -> Option<Hover> {
+pub fn create_hover(var: &EnvVar, value: Option<&str>, groups: &Groups) -> Option<Hover> {
     let mut markdown = String::new();
 
     markdown.push_str(&format!("**Type:** `{}`\n\n", format_type(&var.var_type)));
 
+    if let Some(value) = value {
+        markdown.push_str(&format!("**Value:** `{}`\n\n", display_value(var, value)));
+    }
+
     if let Some(desc) = &var.description {
         markdown.push_str(&format!("**Description:** {}\n\n", desc));
     }
 
     if let Some(default) = &var.default {
-        markdown.push_str(&format!("**Default:** `{}`\n\n", default));
+        markdown.push_str(&format!("**Default:** `{}`\n\n", display_value(var, default)));
     }
 
-    if var.optional {
-        markdown.push_str("**Required:** `false`\n");
-    } else {
+    if !var.optional {
         markdown.push_str("**Required:** `true`\n");
+    } else if !var.required_in.is_empty() {
+        let profiles: Vec<_> = var.required_in.iter().map(|p| format!("`{}`", p)).collect();
+        markdown.push_str(&format!("**Required:** only in {}\n", profiles.join(", ")));
+    } else {
+        markdown.push_str("**Required:** `false`\n");
+    }
+
+    if let Some(example) = &var.example {
+        markdown.push_str(&format!("\n**Example:** `{}`\n", display_value(var, example)));
+    }
+
+    if !var.allowed_values.is_empty() {
+        let values: Vec<_> = var.allowed_values.iter().map(|v| format!("`{}`", v)).collect();
+        markdown.push_str(&format!("\n**Allowed values:** {}\n", values.join(", ")));
+    }
+
+    if let Some(pattern) = &var.pattern {
+        markdown.push_str(&format!("\n**Pattern:** `{}`\n", pattern));
+    }
+
+    match (var.min, var.max) {
+        (Some(min), Some(max)) => markdown.push_str(&format!("\n**Range:** `{}..={}`\n", min, max)),
+        (Some(min), None) => markdown.push_str(&format!("\n**Minimum:** `{}`\n", min)),
+        (None, Some(max)) => markdown.push_str(&format!("\n**Maximum:** `{}`\n", max)),
+        (None, None) => {}
+    }
+
+    if var.secret {
+        markdown.push_str("\n**Secret:** `true`\n");
+    }
+
+    if let Some(deprecated) = &var.deprecated {
+        let notice = match (deprecated.is_empty(), &var.replaced_by) {
+            (false, _) => deprecated.clone(),
+            (true, Some(replacement)) => format!("use `{}` instead", replacement),
+            (true, None) => "`true`".to_string(),
+        };
+        markdown.push_str(&format!("\n**Deprecated:** {}\n", notice));
     }
 
     if let Some(group) = &var.group {
-        markdown.push_str(&format!("\n**Group:** `{}`", group));
+        let config = groups.get(group);
+        match config.and_then(|c| c.title()) {
+            Some(title) => markdown.push_str(&format!("\n**Group:** {} (`{}`)", title, group)),
+            None => markdown.push_str(&format!("\n**Group:** `{}`", group)),
+        }
+        if let Some(description) = config.and_then(|c| c.description()) {
+            markdown.push_str(&format!("\n\n{}", description));
+        }
     }
 
     Some(Hover {
@@ -37,10 +85,13 @@ pub fn create_hover(var: &EnvVar) -> Option<Hover> {
 }
 
 fn format_type(env_type: &crate::types::EnvType) -> String {
-    match env_type {
-        crate::types::EnvType::String => "string".to_string(),
-        crate::types::EnvType::Boolean => "boolean".to_string(),
-        crate::types::EnvType::Number => "number".to_string(),
-        crate::types::EnvType::Integer => "integer".to_string(),
+    env_type.name().to_string()
+}
+
+fn display_value(var: &EnvVar, value: &str) -> String {
+    if var.secret {
+        mask_value(value)
+    } else {
+        value.to_string()
     }
 }
@@ -0,0 +1,114 @@
+use crate::env_file::parse_env_value;
+use crate::secrets::is_secret_name;
+use crate::types::{EnvType, EnvVar};
+use regex::Regex;
+use tower_lsp::lsp_types::Url;
+
+/// Infers schema variables from the contents of an existing env file.
+///
+/// Comment lines directly above a variable become its description. Comment
+/// headers become groups: a comment block standing on its own between blank
+/// lines, or the first line of a section's leading comment block when that
+/// block has several lines, the section holds several variables, or the file
+/// already used headers before.
+/// Commented-out assignments such as `#DEBUG=false` are optional variables.
+pub fn infer_variables(content: &str) -> Vec<EnvVar> {
+    let var_regex = Regex::new(r"^\s*(#\s*)?(?:export\s+)?([A-Za-z_][A-Za-z0-9_]*)\s*=\s*(.*)$").unwrap();
+
+    let mut variables: Vec<EnvVar> = Vec::new();
+    let mut group: Option<String> = None;
+
+    for section in content.split('\n').collect::<Vec<_>>().split(|l| l.trim().is_empty()) {
+        if section.is_empty() {
+            continue;
+        }
+
+        let var_count = section.iter().filter(|l| var_regex.is_match(l)).count();
+        let leading: Vec<String> = section
+            .iter()
+            .take_while(|l| !var_regex.is_match(l))
+            .filter_map(|l| comment_text(l))
+            .collect();
+
+        let mut comments = leading;
+        if var_count == 0 {
+            if let Some(header) = comments.last() {
+                group = Some(header.clone());
+            }
+            continue;
+        }
+        if !comments.is_empty() && (comments.len() > 1 || var_count > 1 || group.is_some()) {
+            group = Some(comments.remove(0));
+        }
+
+        let mut in_leading = true;
+        for line in section {
+            let Some(captures) = var_regex.captures(line) else {
+                if !in_leading {
+                    comments.extend(comment_text(line));
+                }
+                continue;
+            };
+            in_leading = false;
+
+            let name = captures[2].to_string();
+            if variables.iter().any(|v| v.name == name) {
+                comments.clear();
+                continue;
+            }
+
+            let raw_value = captures[3].trim();
+            let value = if raw_value.is_empty() { String::new() } else { parse_env_value(raw_value) };
+            let secret = is_secret_name(&name);
+
+            variables.push(EnvVar {
+                var_type: infer_type(&value),
+                description: (!comments.is_empty()).then(|| comments.join(" ")),
+                optional: captures.get(1).is_some(),
+                group: group.clone(),
+                example: (!value.is_empty() && !secret).then_some(value),
+                secret,
+                name,
+                ..Default::default()
+            });
+            comments.clear();
+        }
+    }
+
+    variables
+}
+
+/// Guesses the type of a variable from a sample value.
+pub fn infer_type(value: &str) -> EnvType {
+    let is_numeric = value.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'));
+    // Values like `007` or zip codes are identifiers rather than numbers.
+    let leading_zero = value.len() > 1 && value.starts_with('0') && !value.starts_with("0.");
+
+    if value.is_empty() {
+        EnvType::String
+    } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+        EnvType::Boolean
+    } else if value.parse::<i64>().is_ok() && !leading_zero {
+        EnvType::Integer
+    } else if is_numeric && value.parse::<f64>().is_ok() && !leading_zero {
+        EnvType::Number
+    } else if value.contains("://") && Url::parse(value).is_ok() {
+        EnvType::Url
+    } else {
+        EnvType::String
+    }
+}
+
+/// The text of a comment line, without the `#` and decoration such as
+/// `# ==== Database ====`. Returns `None` for other lines and for comments
+/// that are decoration only.
+pub fn comment_text(line: &str) -> Option<String> {
+    let text = line.trim().strip_prefix('#')?;
+    let text = text.trim_matches(|c: char| c.is_whitespace() || matches!(c, '#' | '=' | '-' | '*' | '_'));
+
+    if text.chars().any(char::is_alphanumeric) {
+        Some(text.to_string())
+    } else {
+        None
+    }
+}
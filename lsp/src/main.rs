@@ -1,101 +1,289 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_lsp::{lsp_types::*, Client, LanguageServer, LspService, Server};
 use tracing::{error, info};
 
+mod check;
+mod cli;
 mod code_actions;
+mod codegen;
 mod config;
+mod config_file;
 mod diagnostics;
+mod document;
+mod drift;
 mod env_file;
+mod example;
+mod git;
 mod hover;
+mod infer;
+mod report;
 mod schema;
+mod secrets;
+mod symbols;
+#[cfg(test)]
+mod test_support;
 mod types;
 mod validation;
+mod walk;
+mod workspace;
 
-use types::{Config, EnvVar, EnvFileEntry};
+use codegen::SchemaFormat;
+use types::{EnvVar, EnvFileEntry, Groups};
+use validation::{ValidationError, ValidationResult};
+use workspace::Workspace;
 
 struct Backend {
     client: Client,
-    workspace_root: Arc<RwLock<Option<PathBuf>>>,
-    config: Arc<RwLock<Config>>,
-    schemas: Arc<RwLock<Vec<EnvVar>>>,
-    env_files: Arc<RwLock<HashMap<String, Vec<EnvFileEntry>>>>,
+    /// Workspace folders opened by the client.
+    folders: Arc<RwLock<Vec<PathBuf>>>,
+    /// The workspace folders and every package below them with configuration
+    /// of its own, each validated with that configuration.
+    workspaces: Arc<RwLock<Vec<Workspace>>>,
+    /// Schema files that currently have conflict diagnostics, so they can be
+    /// cleared once the conflict is resolved.
+    schema_diagnostic_uris: Arc<RwLock<HashSet<Url>>>,
+    /// Env files open in the editor, revalidated when anything they depend
+    /// on changes on disk.
+    open_files: Arc<RwLock<HashSet<PathBuf>>>,
+    /// Text of the documents open in the editor, kept in sync with their
+    /// incremental changes.
+    documents: Arc<RwLock<HashMap<Url, String>>>,
+    client_capabilities: Arc<RwLock<ClientCapabilities>>,
 }
 
+/// The result of validating one env file.
+struct FileValidation {
+    entries: Vec<EnvFileEntry>,
+    /// The variables of the schemas that apply to the file.
+    schema_vars: Vec<EnvVar>,
+    results: Vec<(Option<String>, ValidationResult)>,
+}
+
+const FILE_WATCHER_REGISTRATION_ID: &str = "env-checker-file-watchers";
+
 impl Backend {
     fn new(client: Client) -> Self {
         Self {
             client,
-            workspace_root: Arc::new(RwLock::new(None)),
-            config: Arc::new(RwLock::new(Config::default())),
-            schemas: Arc::new(RwLock::new(Vec::new())),
-            env_files: Arc::new(RwLock::new(HashMap::new())),
+            folders: Arc::new(RwLock::new(Vec::new())),
+            workspaces: Arc::new(RwLock::new(Vec::new())),
+            schema_diagnostic_uris: Arc::new(RwLock::new(HashSet::new())),
+            open_files: Arc::new(RwLock::new(HashSet::new())),
+            documents: Arc::new(RwLock::new(HashMap::new())),
+            client_capabilities: Arc::new(RwLock::new(ClientCapabilities::default())),
         }
     }
 
-    async fn reload_schemas(&self) {
-        let workspace_root = self.workspace_root.read().await;
-        let config = self.config.read().await;
-        
-        if let Some(root) = workspace_root.as_ref() {
-            let schema_sources = match config::discover_schemas(root, &config) {
-                Ok(s) => s,
-                Err(e) => {
-                    error!("Failed to discover schemas: {:?}", e);
-                    return;
+    /// Finds every configured package below the workspace folders and loads
+    /// its configuration, schemas and env files from scratch.
+    async fn reload_workspaces(&self) {
+        let mut roots: Vec<PathBuf> = Vec::new();
+        for folder in self.folders.read().await.iter() {
+            for root in workspace::find_config_roots(folder) {
+                if !roots.contains(&root) {
+                    roots.push(root);
                 }
-            };
-            
-            let mut all_vars = Vec::new();
-            let mut seen_names = std::collections::HashSet::new();
-            
-            for source in &schema_sources {
-                if let Ok(Some(parsed)) = schema::parse_schema(source) {
-                    for var in parsed.variables {
-                        if seen_names.insert(var.name.clone()) {
-                            all_vars.push(var);
-                        }
-                    }
+            }
+        }
+
+        let workspaces = roots
+            .iter()
+            .map(|root| {
+                let nested = roots
+                    .iter()
+                    .filter(|other| *other != root && other.starts_with(root))
+                    .cloned()
+                    .collect();
+                let mut workspace = Workspace::new(root.clone(), nested);
+                workspace.reload_schemas(None);
+                workspace.load_env_files();
+                workspace
+            })
+            .collect();
+
+        *self.workspaces.write().await = workspaces;
+    }
+
+    /// Publishes cross-schema conflicts as warnings on both schema files
+    /// involved, and clears files whose conflicts went away.
+    async fn publish_schema_diagnostics(&self) {
+        let workspaces = self.workspaces.read().await;
+        let mut by_uri: HashMap<Url, Vec<Diagnostic>> = HashMap::new();
+
+        for conflict in workspaces.iter().flat_map(|w| &w.conflicts) {
+            for on_second in [false, true] {
+                let location = if on_second { conflict.second_location() } else { conflict.first_location() };
+                if let Ok(uri) = Url::from_file_path(location.source.file_path()) {
+                    by_uri
+                        .entry(uri)
+                        .or_default()
+                        .push(diagnostics::create_schema_conflict_diagnostic(conflict, on_second));
                 }
             }
+        }
 
-            info!("Loaded {} environment variable definitions", all_vars.len());
-            *self.schemas.write().await = all_vars;
+        let mut published = self.schema_diagnostic_uris.write().await;
+        for uri in published.drain().filter(|uri| !by_uri.contains_key(uri)).collect::<Vec<_>>() {
+            self.client.publish_diagnostics(uri, Vec::new(), None).await;
+        }
+        for (uri, diagnostics) in by_uri {
+            published.insert(uri.clone());
+            self.client.publish_diagnostics(uri, diagnostics, None).await;
         }
     }
 
-    async fn load_env_files(&self) {
-        let workspace_root = self.workspace_root.read().await;
-        let config = self.config.read().await;
-        
-        if let Some(root) = workspace_root.as_ref() {
-            let env_file_paths = match config::get_env_file_paths(root, &config) {
-                Ok(p) => p,
-                Err(e) => {
-                    error!("Failed to get env file paths: {:?}", e);
-                    return;
-                }
+    /// Asks the client to watch schema, env and config files, replacing an
+    /// earlier registration so patterns follow configuration changes.
+    async fn register_file_watchers(&self, replace: bool) {
+        let supported = self
+            .client_capabilities
+            .read()
+            .await
+            .workspace
+            .as_ref()
+            .and_then(|w| w.did_change_watched_files.as_ref())
+            .and_then(|c| c.dynamic_registration)
+            .unwrap_or(false);
+        if !supported || self.folders.read().await.is_empty() {
+            return;
+        }
+
+        if replace {
+            let unregistration = Unregistration {
+                id: FILE_WATCHER_REGISTRATION_ID.to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
             };
-            
-            let mut env_files = HashMap::new();
-            
-            for path in env_file_paths {
-                if let Ok(entries) = env_file::parse_env_file(Path::new(&path)) {
-                    env_files.insert(path, entries);
+            if let Err(e) = self.client.unregister_capability(vec![unregistration]).await {
+                error!("Failed to unregister file watchers: {:?}", e);
+            }
+        }
+
+        let mut patterns: Vec<String> = Vec::new();
+        for workspace in self.workspaces.read().await.iter() {
+            for pattern in config::watch_patterns(&workspace.config) {
+                if !patterns.contains(&pattern) {
+                    patterns.push(pattern);
                 }
             }
+        }
+        for marker in workspace::PACKAGE_MARKERS {
+            let pattern = format!("**/{}", marker);
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+
+        let watchers = patterns
+            .into_iter()
+            .map(|pattern| FileSystemWatcher {
+                glob_pattern: GlobPattern::String(pattern),
+                kind: None,
+            })
+            .collect();
+        let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+        let registration = Registration {
+            id: FILE_WATCHER_REGISTRATION_ID.to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: serde_json::to_value(options).ok(),
+        };
+
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            error!("Failed to register file watchers: {:?}", e);
+        }
+    }
+
+    /// The editor's text of a document, or its contents on disk when it is
+    /// not open.
+    async fn document_text(&self, uri: &Url) -> Option<String> {
+        if let Some(text) = self.documents.read().await.get(uri) {
+            return Some(text.clone());
+        }
+        std::fs::read_to_string(uri.to_file_path().ok()?).ok()
+    }
+
+    /// Publishes problems in a `.envchecker.json`, or clears them once the
+    /// file is gone.
+    async fn publish_config_diagnostics(&self, config_path: &Path) {
+        let Ok(uri) = Url::from_file_path(config_path) else {
+            return;
+        };
+        let config_dir = config_path.parent().unwrap_or(config_path);
+
+        let diagnostics = match self.document_text(&uri).await {
+            Some(text) => config_file::check_config(&text, config_dir),
+            None => Vec::new(),
+        };
+        self.client.publish_diagnostics(uri, diagnostics, None).await;
+    }
+
+    async fn revalidate_open_files(&self) {
+        let open_files: Vec<PathBuf> = self.open_files.read().await.iter().cloned().collect();
+        for file_path in open_files {
+            self.validate_and_publish_diagnostics(&file_path).await;
+        }
+    }
 
-            info!("Loaded {} .env files", env_files.len());
-            *self.env_files.write().await = env_files;
+    async fn load_env_files(&self) {
+        for workspace in self.workspaces.write().await.iter_mut() {
+            workspace.load_env_files();
         }
     }
 
+    /// Validates an env file against the schemas of its package, once per
+    /// profile that contains it.
+    ///
+    /// Files outside any profile are validated against the merged contents of
+    /// the loaded env files in the same package, with the file's own entries
+    /// taking precedence.
+    async fn validate_file(&self, file_path: &Path) -> FileValidation {
+        let workspaces = self.workspaces.read().await;
+        let workspace = workspace::workspace_for(&workspaces, file_path);
+
+        let file_str = Url::from_file_path(file_path)
+            .map(|u| u.to_string())
+            .unwrap_or_default();
+
+        let entries = workspace
+            .and_then(|w| w.env_files.get(&file_str))
+            .cloned()
+            .or_else(|| env_file::parse_env_file(file_path).ok())
+            .unwrap_or_default();
+
+        let Some(workspace) = workspace else {
+            let result = validation::validate(&[], &Default::default(), &entries, None);
+            return FileValidation { entries, schema_vars: Vec::new(), results: vec![(None, result)] };
+        };
+
+        let schema_vars = workspace.schema_vars_for(file_path);
+        let rules = workspace.rules_for(file_path);
+        let root = &workspace.root;
+        let config = &workspace.config;
+        let profiles = config::profiles_containing(root, config, file_path);
+
+        let results = if profiles.is_empty() {
+            let package_entries = workspace.package_env_entries(file_path);
+            let merged_entries = env_file::merge_env_files(vec![entries.clone(), package_entries]);
+
+            vec![(None, validation::validate(&schema_vars, &rules, &merged_entries, None))]
+        } else {
+            profiles
+                .into_iter()
+                .map(|profile| {
+                    let paths = config::get_profile_file_paths(root, config, &profile);
+                    let layered = env_file::load_layered_env_files(&paths);
+                    let result = validation::validate(&schema_vars, &rules, &layered, Some(&profile));
+                    (Some(profile), result)
+                })
+                .collect()
+        };
+
+        FileValidation { entries, schema_vars, results }
+    }
+
     async fn validate_and_publish_diagnostics(&self, file_path: &Path) {
-        let schemas = self.schemas.read().await;
-        let env_files = self.env_files.read().await;
-        
         let file_uri = match Url::from_file_path(file_path) {
             Ok(uri) => uri,
             Err(_) => {
@@ -103,71 +291,103 @@ impl Backend {
                 return;
             }
         };
-        
-        let file_str = file_uri.to_string();
-        
-        let file_entries = env_files.get(&file_str).cloned().unwrap_or_default();
-        let all_entries: Vec<_> = env_files.values().flat_map(|v| v.iter().cloned()).collect();
-        
-        let merged_entries = env_file::merge_env_files(vec![file_entries, all_entries]);
-        
-        let validation = validation::validate(&*schemas, &merged_entries);
-        
+
+        let FileValidation { entries: file_entries, schema_vars, results } = self.validate_file(file_path).await;
+
         let mut diagnostics = Vec::new();
-        
-        for error in &validation.errors {
-            diagnostics.push(diagnostics::create_missing_var_diagnostic(error));
-        }
+        let mut missing: BTreeMap<String, (ValidationError, Vec<String>)> = BTreeMap::new();
+        let mut published = HashSet::new();
+
+        let file_path_str = file_path.to_string_lossy();
+
+        for (profile, validation) in &results {
+            for error in &validation.errors {
+                match &error.entry {
+                    None => {
+                        let (_, profiles) = missing
+                            .entry(error.var_name.clone())
+                            .or_insert_with(|| (error.clone(), Vec::new()));
+                        profiles.extend(profile.clone());
+                    }
+                    Some(entry) if entry.file_path == file_path_str => {
+                        if published.insert((entry.line, error.var_name.clone())) {
+                            diagnostics.push(diagnostics::create_entry_diagnostic(error));
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
 
-        self.client.publish_diagnostics(file_uri, diagnostics, None).await;
-    }
+            for warning in &validation.warnings {
+                let Some(entry) = warning.entry.as_ref().filter(|e| e.file_path == file_path_str) else {
+                    continue;
+                };
+                if published.insert((entry.line, format!("deprecated:{}", warning.var_name))) {
+                    diagnostics.push(diagnostics::create_deprecated_var_diagnostic(warning));
+                }
+            }
 
-    async fn get_last_line_info(&self, file_path: &str) -> tower_lsp::jsonrpc::Result<(usize, Option<String>)> {
-        let env_files = self.env_files.read().await;
-        
-        if let Some(entries) = env_files.get(file_path) {
-            if let Some(last_entry) = entries.last() {
-                return Ok((last_entry.line + 1, Some(format!("{}={}", 
-                    last_entry.name, 
-                    last_entry.value.as_deref().unwrap_or("")))));
+            for entry in validation.unused_entries() {
+                if entry.file_path == file_path_str && published.insert((entry.line, format!("unused:{}", entry.name))) {
+                    diagnostics.push(diagnostics::create_unused_var_diagnostic(&entry.name, entry.line, entry.column));
+                }
             }
         }
-        
-        Ok((0, None))
+
+        for (error, profiles) in missing.values() {
+            diagnostics.push(diagnostics::create_missing_var_diagnostic(error, profiles));
+        }
+
+        for leak in secrets::find_leaked_secrets(&file_entries, &schema_vars, file_path) {
+            diagnostics.push(diagnostics::create_secret_leak_diagnostic(&leak));
+        }
+
+        self.client.publish_diagnostics(file_uri, diagnostics, None).await;
     }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> tower_lsp::jsonrpc::Result<InitializeResult> {
-        if let Some(root_uri) = params.root_uri {
-            let root_path = root_uri.to_file_path().map_err(|e| {
+        *self.client_capabilities.write().await = params.capabilities;
+
+        let folder_uris: Vec<Url> = match params.workspace_folders {
+            Some(folders) if !folders.is_empty() => folders.into_iter().map(|f| f.uri).collect(),
+            _ => params.root_uri.into_iter().collect(),
+        };
+
+        let mut folders = Vec::new();
+        for uri in folder_uris {
+            let root_path = uri.to_file_path().map_err(|e| {
                 tower_lsp::jsonrpc::Error::invalid_params(format!("Failed to convert root URI to path: {:?}", e))
             })?;
-            
-            *self.workspace_root.write().await = Some(root_path.clone());
-            
-            if let Ok(Some(config)) = config::load_config(&root_path) {
-                *self.config.write().await = config;
-                info!("Loaded configuration from .envchecker.json");
-            }
-            
-            self.reload_schemas().await;
-            self.load_env_files().await;
+            folders.push(root_path);
         }
 
+        *self.folders.write().await = folders;
+        self.reload_workspaces().await;
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::INCREMENTAL)),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
                 code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
                 completion_provider: Some(CompletionOptions {
                     resolve_provider: Some(false),
-                    trigger_characters: Some(vec!["=".to_string()]),
+                    trigger_characters: Some(vec!["=".to_string(), "\"".to_string()]),
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
                     completion_item: None,
                 }),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                        supported: Some(true),
+                        change_notifications: Some(OneOf::Left(true)),
+                    }),
+                    file_operations: None,
+                }),
                 ..Default::default()
             },
             server_info: None,
@@ -176,6 +396,20 @@ impl LanguageServer for Backend {
 
     async fn initialized(&self, _: InitializedParams) {
         info!("Server initialized");
+        self.register_file_watchers(false).await;
+        self.publish_schema_diagnostics().await;
+
+        let config_paths: Vec<PathBuf> = self
+            .workspaces
+            .read()
+            .await
+            .iter()
+            .map(|w| w.root.join(config_file::CONFIG_FILE_NAME))
+            .filter(|path| path.exists())
+            .collect();
+        for config_path in config_paths {
+            self.publish_config_diagnostics(&config_path).await;
+        }
     }
 
     async fn shutdown(&self) -> tower_lsp::jsonrpc::Result<()> {
@@ -185,47 +419,210 @@ impl LanguageServer for Backend {
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let uri = params.text_document.uri;
+        self.documents.write().await.insert(uri.clone(), params.text_document.text);
         let file_path = match uri.to_file_path() {
             Ok(p) => p,
             Err(_) => return,
         };
 
-        if file_path.extension().and_then(|e| e.to_str()) == Some("env") {
+        if config_file::is_config_file(&file_path) {
+            self.publish_config_diagnostics(&file_path).await;
+        }
+
+        if env_file::is_env_file(&file_path) {
+            self.open_files.write().await.insert(file_path.clone());
             self.load_env_files().await;
             self.validate_and_publish_diagnostics(&file_path).await;
         }
     }
 
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.documents.write().await.remove(&params.text_document.uri);
+        if let Ok(file_path) = params.text_document.uri.to_file_path() {
+            self.open_files.write().await.remove(&file_path);
+        }
+    }
+
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri;
+        if let Some(text) = self.documents.write().await.get_mut(&uri) {
+            for change in &params.content_changes {
+                document::apply_change(text, change);
+            }
+        }
         let file_path = match uri.to_file_path() {
             Ok(p) => p,
             Err(_) => return,
         };
 
-        if file_path.extension().and_then(|e| e.to_str()) == Some("env") {
+        if config_file::is_config_file(&file_path) {
+            self.publish_config_diagnostics(&file_path).await;
+        }
+
+        if env_file::is_env_file(&file_path) {
             self.load_env_files().await;
             self.validate_and_publish_diagnostics(&file_path).await;
         }
     }
 
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        {
+            let mut folders = self.folders.write().await;
+            for removed in params.event.removed {
+                if let Ok(path) = removed.uri.to_file_path() {
+                    folders.retain(|f| *f != path);
+                }
+            }
+            for added in params.event.added {
+                if let Ok(path) = added.uri.to_file_path() {
+                    if !folders.contains(&path) {
+                        folders.push(path);
+                    }
+                }
+            }
+        }
+
+        self.reload_workspaces().await;
+        self.register_file_watchers(true).await;
+        self.publish_schema_diagnostics().await;
+        self.revalidate_open_files().await;
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let paths: HashSet<PathBuf> = params
+            .changes
+            .iter()
+            .filter_map(|change| change.uri.to_file_path().ok())
+            .collect();
+
+        let packages_changed = paths.iter().any(|p| {
+            p.file_name()
+                .is_some_and(|n| workspace::PACKAGE_MARKERS.iter().any(|marker| n == *marker))
+        });
+
+        if packages_changed {
+            info!("Configuration or package layout changed, reloading workspaces");
+            self.reload_workspaces().await;
+            self.register_file_watchers(true).await;
+        } else {
+            for workspace in self.workspaces.write().await.iter_mut() {
+                let owned: Vec<&PathBuf> = paths.iter().filter(|p| workspace.owns(p)).collect();
+                if owned.is_empty() {
+                    continue;
+                }
+
+                let known_schemas: HashSet<PathBuf> = workspace
+                    .parsed_schemas
+                    .iter()
+                    .map(|(source, _)| PathBuf::from(source.file_path()))
+                    .collect();
+                let is_schema = |p: &PathBuf| {
+                    known_schemas.contains(p)
+                        || matches!(p.extension().and_then(|e| e.to_str()), Some("yml") | Some("yaml"))
+                        || schema::detect::detect_schema(p).is_some()
+                };
+                // Files that are neither schemas nor env files, such as
+                // ordinary source files, are left alone.
+                let (schema_paths, other_paths): (HashSet<PathBuf>, HashSet<PathBuf>) =
+                    owned.into_iter().cloned().partition(|p| is_schema(p));
+                let env_paths: Vec<PathBuf> = other_paths.into_iter().filter(|p| env_file::is_env_file(p)).collect();
+
+                if !schema_paths.is_empty() {
+                    workspace.reload_schemas(Some(&schema_paths));
+                }
+                if !env_paths.is_empty() {
+                    workspace.load_env_files();
+                }
+            }
+        }
+
+        for config_path in paths.iter().filter(|p| config_file::is_config_file(p)) {
+            self.publish_config_diagnostics(config_path).await;
+        }
+        self.publish_schema_diagnostics().await;
+        self.revalidate_open_files().await;
+    }
+
+    async fn completion(&self, params: CompletionParams) -> tower_lsp::jsonrpc::Result<Option<CompletionResponse>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let is_config = uri.to_file_path().is_ok_and(|p| config_file::is_config_file(&p));
+        if !is_config {
+            return Ok(None);
+        }
+
+        let Some(text) = self.document_text(&uri).await else {
+            return Ok(None);
+        };
+        Ok(Some(CompletionResponse::Array(config_file::complete_config(&text, position))))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<DocumentSymbolResponse>> {
+        let uri = params.text_document.uri;
+        let Ok(file_path) = uri.to_file_path() else {
+            return Ok(None);
+        };
+        if !env_file::is_env_file(&file_path) {
+            return Ok(None);
+        }
+        let Some(text) = self.document_text(&uri).await else {
+            return Ok(None);
+        };
+
+        let (schema_vars, groups) = match workspace::workspace_for(&self.workspaces.read().await, &file_path) {
+            Some(workspace) => (workspace.schema_vars_for(&file_path), workspace.config.groups.clone()),
+            None => (Vec::new(), Groups::new()),
+        };
+        let outline = symbols::document_symbols(&text, &schema_vars, &groups);
+
+        let hierarchical = self
+            .client_capabilities
+            .read()
+            .await
+            .text_document
+            .as_ref()
+            .and_then(|t| t.document_symbol.as_ref())
+            .and_then(|d| d.hierarchical_document_symbol_support)
+            .unwrap_or(false);
+
+        Ok(Some(if hierarchical {
+            DocumentSymbolResponse::Nested(outline)
+        } else {
+            DocumentSymbolResponse::Flat(symbols::flatten_symbols(&uri, outline, None))
+        }))
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> tower_lsp::jsonrpc::Result<Option<Vec<SymbolInformation>>> {
+        let workspaces = self.workspaces.read().await;
+        Ok(Some(symbols::workspace_symbols(&workspaces, &params.query)))
+    }
+
     async fn hover(&self, params: HoverParams) -> tower_lsp::jsonrpc::Result<Option<Hover>> {
         let uri = params.text_document_position_params.text_document.uri;
         let position = params.text_document_position_params.position;
         
-        let file_path = match uri.to_file_path() {
-            Ok(p) => p,
-            Err(_) => return Ok(None),
+        let Ok(file_path) = uri.to_file_path() else {
+            return Ok(None);
         };
 
-        let env_files = self.env_files.read().await;
+        let workspaces = self.workspaces.read().await;
+        let Some(workspace) = workspace::workspace_for(&workspaces, &file_path) else {
+            return Ok(None);
+        };
         let file_str = uri.to_string();
         
-        if let Some(entries) = env_files.get(&file_str) {
+        if let Some(entries) = workspace.env_files.get(&file_str) {
             if let Some(entry) = entries.iter().find(|e| e.line as u32 == position.line) {
-                let schemas = self.schemas.read().await;
+                let schemas = workspace.schema_vars_for(&file_path);
                 if let Some(var) = schemas.iter().find(|v| v.name == entry.name) {
-                    return Ok(hover::create_hover(var));
+                    return Ok(hover::create_hover(var, entry.value.as_deref(), &workspace.config.groups));
                 }
             }
         }
@@ -235,58 +632,157 @@ impl LanguageServer for Backend {
 
     async fn code_action(&self, params: CodeActionParams) -> tower_lsp::jsonrpc::Result<Option<Vec<CodeActionOrCommand>>> {
         let uri = params.text_document.uri;
-        match uri.to_file_path() {
+        let file_path = match uri.to_file_path() {
             Ok(p) => p,
             Err(_) => return Ok(None),
         };
+        let file_path_str = file_path.to_string_lossy();
 
         let mut actions = Vec::new();
-        
-        let schemas = self.schemas.read().await;
-        let env_files = self.env_files.read().await;
-        let file_str = uri.to_string();
-        
-        let validation = if let Some(entries) = env_files.get(&file_str) {
-            let all_entries: Vec<_> = env_files.values().flat_map(|v| v.iter().cloned()).collect();
-            let merged = env_file::merge_env_files(vec![entries.clone(), all_entries]);
-            validation::validate(&*schemas, &merged)
-        } else {
-            validation::validate(&*schemas, &[])
+
+        let FileValidation { entries: file_entries, schema_vars: schemas, results } = self.validate_file(&file_path).await;
+
+        // Generated files go to the root of the env file's package, or next
+        // to the env file when no workspace is open.
+        let (groups, output_dir) = match workspace::workspace_for(&self.workspaces.read().await, &file_path) {
+            Some(workspace) => (
+                workspace.config.groups.clone(),
+                workspace::package_root(&file_path, &workspace.root),
+            ),
+            None => (Groups::new(), file_path.parent().map(Path::to_path_buf).unwrap_or_default()),
         };
 
-        if !validation.errors.is_empty() {
-            let missing_vars: Vec<_> = validation.errors
-                .iter()
-                .map(|e| e.schema_var.clone())
-                .collect();
+        let mut missing_vars: Vec<EnvVar> = Vec::new();
+        for error in results.iter().flat_map(|(_, v)| &v.errors).filter(|e| e.is_missing()) {
+            if !missing_vars.iter().any(|v| v.name == error.var_name) {
+                // Required here, by a profile or a rule, even if optional elsewhere.
+                let mut var = error.schema_var.clone();
+                var.optional = false;
+                missing_vars.push(var);
+            }
+        }
 
-            let (last_line, last_content) = self.get_last_line_info(&file_str).await?;
+        if !missing_vars.is_empty() {
+            let (last_line, last_content) = file_entries.last().map_or((0, None), |last_entry| {
+                (last_entry.line + 1, Some(format!("{}={}",
+                    last_entry.name,
+                    last_entry.value.as_deref().unwrap_or(""))))
+            });
 
             actions.push(code_actions::create_append_missing_action(
                 &missing_vars,
-                &uri.to_string(),
+                &groups,
+                uri.as_ref(),
                 last_line,
                 last_content,
             ));
         }
 
-        actions.push(code_actions::create_create_example_action(
-            &schemas,
-            &uri.to_string(),
-        ));
+        let mut offered = HashSet::new();
+        for warning in results.iter().flat_map(|(_, v)| &v.warnings) {
+            let (Some(entry), validation::ValidationErrorKind::Deprecated { replacement: Some(replacement) }) =
+                (&warning.entry, &warning.kind)
+            else {
+                continue;
+            };
+
+            let line = entry.line as u32;
+            if entry.file_path != file_path_str
+                || line < params.range.start.line
+                || line > params.range.end.line
+                || !offered.insert(line)
+            {
+                continue;
+            }
 
-        if !validation.errors.is_empty() {
-            Ok(Some(actions))
+            let diagnostic = params.context.diagnostics.iter().find(|d| {
+                d.range.start.line == line
+                    && d.code == Some(NumberOrString::String("deprecated-env-var".to_string()))
+            });
+
+            actions.push(code_actions::create_rename_deprecated_action(
+                entry,
+                replacement,
+                uri.as_ref(),
+                diagnostic.cloned(),
+            ));
+        }
+
+        if schemas.is_empty() {
+            let env_file_name = file_path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            let inferred = std::fs::read_to_string(&file_path)
+                .map(|content| infer::infer_variables(&content))
+                .unwrap_or_default();
+
+            for format in [SchemaFormat::Yaml, SchemaFormat::Zod, SchemaFormat::Pydantic] {
+                let schema_path = output_dir.join(format.default_file_name());
+                if inferred.is_empty() || schema_path.exists() {
+                    continue;
+                }
+                if let Ok(schema_uri) = Url::from_file_path(&schema_path) {
+                    actions.push(code_actions::create_generate_schema_action(
+                        &inferred,
+                        format,
+                        &schema_uri,
+                        &env_file_name,
+                    ));
+                }
+            }
         } else {
-            Ok(Some(vec![actions[0].clone()]))
+            let example_path = output_dir.join(".env.example");
+
+            if let Ok(example_uri) = Url::from_file_path(&example_path) {
+                match std::fs::read_to_string(&example_path) {
+                    Ok(existing) => actions.extend(code_actions::create_update_example_action(
+                        &schemas,
+                        &groups,
+                        &example_uri,
+                        &existing,
+                    )),
+                    Err(_) => actions.push(code_actions::create_create_example_action(&schemas, &groups, &example_uri)),
+                }
+            }
         }
+
+        Ok(Some(actions))
     }
 }
 
 #[tokio::main]
 async fn main() {
+    let command = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    match command {
+        cli::Command::Serve => {}
+        cli::Command::Help => {
+            print!("{}", cli::USAGE);
+            return;
+        }
+        cli::Command::Check(args) => {
+            tracing_subscriber::fmt()
+                .with_max_level(tracing::Level::WARN)
+                .with_writer(std::io::stderr)
+                .init();
+            std::process::exit(cli::run_check(args));
+        }
+        cli::Command::Generate(args) => {
+            tracing_subscriber::fmt()
+                .with_max_level(tracing::Level::WARN)
+                .with_writer(std::io::stderr)
+                .init();
+            std::process::exit(cli::run_generate(args));
+        }
+    }
+
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::INFO)
+        .with_writer(std::io::stderr)
         .init();
 
     info!("Starting Environment Checker LSP");
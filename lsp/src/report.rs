@@ -0,0 +1,545 @@
+use crate::check::{CheckReport, CheckTarget, Finding};
+use crate::env_file;
+use crate::types::{EnvVar, SchemaLocation};
+use crate::workspace;
+use serde_json::{json, Value};
+use std::path::Path;
+use tower_lsp::lsp_types::DiagnosticSeverity;
+
+const TOOL_NAME: &str = "env-checker";
+const TOOL_URI: &str = "https://github.com/DCC-BS/env-checker";
+
+/// Rules reported by the checker, with a short description for SARIF.
+const RULES: &[(&str, &str)] = &[
+    ("missing-env-var", "A required environment variable is not set"),
+    ("invalid-env-value", "A value does not match the type or constraints declared in the schema"),
+    ("env-var-rule", "A requirement between variables (requires, requires_if, one_of, mutually_exclusive) is violated"),
+    ("deprecated-env-var", "A deprecated environment variable is still set"),
+    ("secret-leak", "A real-looking secret is stored in a file that is committed"),
+    ("unused-env-var", "An environment variable is not declared in any schema"),
+    ("schema-conflict", "Two schemas declare the same variable with a different type, default or requiredness"),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    #[default]
+    Human,
+    Json,
+    Sarif,
+    Junit,
+}
+
+impl ReportFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "human" | "text" => Some(Self::Human),
+            "json" => Some(Self::Json),
+            "sarif" => Some(Self::Sarif),
+            "junit" => Some(Self::Junit),
+            _ => None,
+        }
+    }
+}
+
+pub fn render(report: &CheckReport, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Human => render_human(report),
+        ReportFormat::Json => render_json(report),
+        ReportFormat::Sarif => render_sarif(report),
+        ReportFormat::Junit => render_junit(report),
+    }
+}
+
+pub fn render_human(report: &CheckReport) -> String {
+    let findings = report.findings();
+    let mut output = String::new();
+
+    for finding in &findings {
+        output.push_str(&format_finding(finding, &report.workspace_root));
+        output.push('\n');
+    }
+
+    if !findings.is_empty() {
+        output.push('\n');
+    }
+
+    let (errors, warnings, _) = count_by_severity(&findings);
+    let scope = match &report.environment {
+        Some(source) if source == env_file::PROCESS_ENV_SOURCE => "the process environment".to_string(),
+        Some(source) => format!("the environment in {}", relative_path(source, &report.workspace_root)),
+        None if report.targets.iter().any(|t| t.profile.is_some()) => {
+            format!("{} profile(s)", report.targets.len())
+        }
+        None => {
+            let files: usize = report.targets.iter().map(|t| t.files.len()).sum();
+            format!("{} env file(s)", files)
+        }
+    };
+
+    output.push_str(&format!(
+        "Checked {} variable(s) from {} schema(s) against {}: {} error(s), {} warning(s)\n",
+        report.schema_vars.len(),
+        report.schema_sources.len(),
+        scope,
+        errors,
+        warnings
+    ));
+
+    output
+}
+
+fn format_finding(finding: &Finding, workspace_root: &Path) -> String {
+    let diagnostic = &finding.diagnostic;
+    let start = diagnostic.range.start;
+    let message = diagnostic.message.replace('\n', "\n    ");
+
+    format!(
+        "{}:{}:{}: {}[{}]: {}",
+        relative_path(&finding.file_path, workspace_root),
+        start.line + 1,
+        start.character + 1,
+        severity_name(diagnostic.severity),
+        finding.rule_id(),
+        message
+    )
+}
+
+pub fn render_json(report: &CheckReport) -> String {
+    let root = &report.workspace_root;
+    let findings = report.findings();
+    let (errors, warnings, infos) = count_by_severity(&findings);
+
+    let findings_json: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let start = finding.diagnostic.range.start;
+            json!({
+                "rule": finding.rule_id(),
+                "severity": severity_name(finding.diagnostic.severity),
+                "message": finding.diagnostic.message,
+                "variable": finding.var_name,
+                "profile": finding.profile,
+                "location": {
+                    "file": relative_path(&finding.file_path, root),
+                    "line": start.line + 1,
+                    "column": start.character + 1,
+                },
+                "schemaLocation": schema_location(finding.schema_var.as_ref()).map(|loc| json!({
+                    "file": relative_path(loc.source.file_path(), root),
+                    "line": loc.line + 1,
+                    "column": loc.column + 1,
+                })),
+                "schemaSource": schema_location(finding.schema_var.as_ref()).map(|loc| json!({
+                    "kind": loc.source.kind(),
+                    "file": relative_path(loc.source.file_path(), root),
+                })),
+            })
+        })
+        .collect();
+
+    let document = json!({
+        "workspaceRoot": root.to_string_lossy(),
+        "schemas": report.schema_sources.iter().map(|source| json!({
+            "kind": source.kind(),
+            "file": relative_path(source.file_path(), root),
+        })).collect::<Vec<_>>(),
+        "targets": report.targets.iter().map(|target| json!({
+            "profile": target.profile,
+            "files": target.files.iter().map(|f| relative_path(f, root)).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "findings": findings_json,
+        "summary": {
+            "variables": report.schema_vars.len(),
+            "errors": errors,
+            "warnings": warnings,
+            "infos": infos,
+        },
+    });
+
+    serde_json::to_string_pretty(&document).unwrap_or_default()
+}
+
+/// Renders a SARIF 2.1.0 log, e.g. for GitHub code scanning. Locations in
+/// the workspace are relative to the `%SRCROOT%` base, which points at the
+/// workspace root.
+pub fn render_sarif(report: &CheckReport) -> String {
+    let root = &report.workspace_root;
+    let findings = report.findings();
+
+    let rules: Vec<Value> = RULES
+        .iter()
+        .map(|(id, description)| json!({
+            "id": id,
+            "shortDescription": { "text": description },
+            "defaultConfiguration": { "level": sarif_level(default_severity(id)) },
+        }))
+        .collect();
+
+    let results: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let start = finding.diagnostic.range.start;
+            let end = finding.diagnostic.range.end;
+            let rule_index = RULES.iter().position(|(id, _)| *id == finding.rule_id());
+
+            let mut result = json!({
+                "ruleId": finding.rule_id(),
+                "level": sarif_level(finding.diagnostic.severity),
+                "message": { "text": finding.diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": artifact_location(&finding.file_path, root),
+                        "region": {
+                            "startLine": start.line + 1,
+                            "startColumn": start.character + 1,
+                            "endColumn": end.character.max(start.character) + 1,
+                        },
+                    },
+                }],
+                "properties": {
+                    "variable": finding.var_name,
+                    "profile": finding.profile,
+                },
+            });
+
+            if let Some(index) = rule_index {
+                result["ruleIndex"] = json!(index);
+            }
+
+            if let Some(loc) = schema_location(finding.schema_var.as_ref()) {
+                result["relatedLocations"] = json!([{
+                    "id": 1,
+                    "message": { "text": format!("'{}' is declared in this {} schema", finding.var_name, loc.source.kind().name()) },
+                    "physicalLocation": {
+                        "artifactLocation": artifact_location(loc.source.file_path(), root),
+                        "region": {
+                            "startLine": loc.line + 1,
+                            "startColumn": loc.column + 1,
+                        },
+                    },
+                }]);
+                result["properties"]["schemaSource"] = json!(loc.source.kind().name());
+            }
+
+            result
+        })
+        .collect();
+
+    let root_uri = tower_lsp::lsp_types::Url::from_directory_path(root)
+        .map(|u| u.to_string())
+        .unwrap_or_default();
+
+    let document = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": TOOL_NAME,
+                    "informationUri": TOOL_URI,
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": root_uri },
+            },
+            "results": results,
+        }],
+    });
+
+    serde_json::to_string_pretty(&document).unwrap_or_default()
+}
+
+/// Renders JUnit XML with one test suite per profile (or package) and
+/// one test case per schema variable. Findings about other variables, such
+/// as unused ones, get a test case of their own under
+/// `env-checker.<rule>`, and secret leaks and schema conflicts are reported
+/// in a separate `workspace` suite. Errors become failures; warnings are
+/// attached as `system-out` so they show up without failing the build.
+pub fn render_junit(report: &CheckReport) -> String {
+    let mut suites = String::new();
+    let mut total_tests = 0;
+    let mut total_failures = 0;
+
+    let mut push_suite = |name: &str, cases: Vec<(String, bool)>| {
+        let failures = cases.iter().filter(|(_, failed)| *failed).count();
+        let body: String = cases.iter().map(|(case, _)| case.as_str()).collect();
+
+        suites.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n{}  </testsuite>\n",
+            xml_escape(name),
+            cases.len(),
+            failures,
+            body
+        ));
+        total_tests += cases.len();
+        total_failures += failures;
+    };
+
+    for target in &report.targets {
+        let suite_name = junit_suite_name(target, &report.workspace_root);
+        let target_findings = report.target_findings(target);
+        let classname = format!("{}.{}", TOOL_NAME, suite_name);
+
+        let mut cases: Vec<(String, bool)> = target
+            .schema_vars
+            .iter()
+            .map(|var| {
+                let var_findings: Vec<&Finding> = target_findings.iter().filter(|f| f.var_name == var.name).collect();
+                junit_case(&var.name, &classname, &var_findings, &report.workspace_root)
+            })
+            .collect();
+
+        cases.extend(
+            target_findings
+                .iter()
+                .filter(|f| !target.schema_vars.iter().any(|v| v.name == f.var_name))
+                .map(|f| junit_finding_case(f, &report.workspace_root)),
+        );
+
+        push_suite(&suite_name, cases);
+    }
+
+    let workspace_findings = report.workspace_findings();
+    if !workspace_findings.is_empty() {
+        let cases = workspace_findings
+            .iter()
+            .map(|f| junit_finding_case(f, &report.workspace_root))
+            .collect();
+        push_suite("workspace", cases);
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n{}</testsuites>\n",
+        TOOL_NAME, total_tests, total_failures, suites
+    )
+}
+
+/// Names a suite after its profile, or after its package when packages are
+/// checked one by one.
+fn junit_suite_name(target: &CheckTarget, root: &Path) -> String {
+    if let Some(profile) = &target.profile {
+        return profile.clone();
+    }
+
+    let package = target
+        .files
+        .first()
+        .map(|file| workspace::package_root(Path::new(file), root))
+        .unwrap_or_else(|| root.to_path_buf());
+    match package.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => format!("env:{}", relative.to_string_lossy()),
+        _ => "env".to_string(),
+    }
+}
+
+/// A test case for a single finding, named after its variable and grouped
+/// by rule.
+fn junit_finding_case(finding: &Finding, workspace_root: &Path) -> (String, bool) {
+    let classname = format!("{}.{}", TOOL_NAME, finding.rule_id());
+    junit_case(&finding.var_name, &classname, &[finding], workspace_root)
+}
+
+/// Renders a test case and whether it failed, i.e. has an error among its
+/// findings.
+fn junit_case(name: &str, classname: &str, findings: &[&Finding], workspace_root: &Path) -> (String, bool) {
+    let mut case = format!(
+        "    <testcase name=\"{}\" classname=\"{}\">\n",
+        xml_escape(name),
+        xml_escape(classname)
+    );
+
+    let (errors, others): (Vec<&Finding>, Vec<&Finding>) = findings
+        .iter()
+        .partition(|f| f.diagnostic.severity == Some(DiagnosticSeverity::ERROR));
+
+    for finding in &errors {
+        case.push_str(&format!(
+            "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+            xml_escape(finding.rule_id()),
+            xml_escape(&finding.diagnostic.message),
+            xml_escape(&format_finding(finding, workspace_root))
+        ));
+    }
+
+    let others: Vec<String> = others.iter().map(|f| format_finding(f, workspace_root)).collect();
+    if !others.is_empty() {
+        case.push_str(&format!("      <system-out>{}</system-out>\n", xml_escape(&others.join("\n"))));
+    }
+
+    case.push_str("    </testcase>\n");
+    (case, !errors.is_empty())
+}
+
+/// A SARIF artifact location, relative to `%SRCROOT%` for files in the
+/// workspace and absolute for anything else.
+fn artifact_location(path: &str, workspace_root: &Path) -> Value {
+    if Path::new(path).starts_with(workspace_root) {
+        return json!({
+            "uri": relative_path(path, workspace_root),
+            "uriBaseId": "%SRCROOT%",
+        });
+    }
+
+    let uri = tower_lsp::lsp_types::Url::from_file_path(path).map_or_else(|_| path.to_string(), |u| u.to_string());
+    json!({ "uri": uri })
+}
+
+fn schema_location(var: Option<&EnvVar>) -> Option<&SchemaLocation> {
+    var.and_then(|v| v.location.as_ref())
+}
+
+fn relative_path(path: &str, workspace_root: &Path) -> String {
+    let path = Path::new(path);
+    path.strip_prefix(workspace_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn count_by_severity(findings: &[Finding]) -> (usize, usize, usize) {
+    let count = |severity| {
+        findings
+            .iter()
+            .filter(|f| f.diagnostic.severity == Some(severity))
+            .count()
+    };
+
+    (
+        count(DiagnosticSeverity::ERROR),
+        count(DiagnosticSeverity::WARNING),
+        count(DiagnosticSeverity::INFORMATION) + count(DiagnosticSeverity::HINT),
+    )
+}
+
+fn default_severity(rule_id: &str) -> Option<DiagnosticSeverity> {
+    match rule_id {
+        "missing-env-var" | "invalid-env-value" | "env-var-rule" => Some(DiagnosticSeverity::ERROR),
+        "deprecated-env-var" | "secret-leak" | "schema-conflict" => Some(DiagnosticSeverity::WARNING),
+        _ => Some(DiagnosticSeverity::INFORMATION),
+    }
+}
+
+fn severity_name(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "info",
+    }
+}
+
+fn sarif_level(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        _ => "note",
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check;
+    use crate::test_support::TempDir;
+    use crate::types::Config;
+
+    /// A workspace with a missing variable, an invalid value, a deprecated
+    /// and an unused variable in its `.env`.
+    fn fixture(name: &str) -> (TempDir, CheckReport) {
+        let dir = TempDir::new(&format!("report-{}", name));
+        dir.write(
+            "env.schema.yml",
+            "variables:\n  DATABASE_URL:\n    type: url\n    required: true\n  PORT:\n    type: integer\n  OLD_HOST:\n    type: string\n    deprecated: use DATABASE_URL instead\n",
+        );
+        dir.write(".env", "PORT=eighty\nOLD_HOST=db\nLEGACY=1\n");
+        let report = check::check_workspace(dir.path(), &Config::default(), &[]).unwrap();
+        (dir, report)
+    }
+
+    #[test]
+    fn human_output_lists_findings_with_locations() {
+        let (_dir, report) = fixture("human");
+        let output = render_human(&report);
+
+        assert_eq!(
+            output,
+            format!(
+                "{}\n{}\n{}\n{}\n\nChecked 3 variable(s) from 1 schema(s) against 1 env file(s): 2 error(s), 1 warning(s)\n",
+                ".env:1:1: error[missing-env-var]: Missing required environment variable: 'DATABASE_URL'",
+                ".env:1:1: error[invalid-env-value]: Invalid value for environment variable 'PORT': expected an integer",
+                ".env:2:1: warning[deprecated-env-var]: Environment variable 'OLD_HOST' is deprecated: use DATABASE_URL instead",
+                ".env:3:1: info[unused-env-var]: Environment variable 'LEGACY' is not defined in any schema",
+            )
+        );
+    }
+
+    #[test]
+    fn json_includes_rules_locations_and_schema_sources() {
+        let (_dir, report) = fixture("json");
+        let document: Value = serde_json::from_str(&render_json(&report)).unwrap();
+
+        assert_eq!(document["schemas"], json!([{ "kind": "yaml", "file": "env.schema.yml" }]));
+        assert_eq!(document["summary"], json!({ "variables": 3, "errors": 2, "warnings": 1, "infos": 1 }));
+
+        let missing = &document["findings"][0];
+        assert_eq!(missing["rule"], "missing-env-var");
+        assert_eq!(missing["severity"], "error");
+        assert_eq!(missing["location"], json!({ "file": ".env", "line": 1, "column": 1 }));
+        assert_eq!(missing["schemaLocation"], json!({ "file": "env.schema.yml", "line": 2, "column": 3 }));
+        assert_eq!(missing["schemaSource"], json!({ "kind": "yaml", "file": "env.schema.yml" }));
+
+        let unused = &document["findings"][3];
+        assert_eq!(unused["rule"], "unused-env-var");
+        assert_eq!(unused["schemaLocation"], Value::Null);
+    }
+
+    #[test]
+    fn sarif_results_point_into_the_workspace_and_the_schema() {
+        let (_dir, report) = fixture("sarif");
+        let document: Value = serde_json::from_str(&render_sarif(&report)).unwrap();
+        let run = &document["runs"][0];
+
+        assert_eq!(document["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), RULES.len());
+        assert!(run["originalUriBaseIds"]["%SRCROOT%"]["uri"].as_str().unwrap().starts_with("file://"));
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "missing-env-var");
+        assert_eq!(result["ruleIndex"], 0);
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"],
+            json!({ "uri": ".env", "uriBaseId": "%SRCROOT%" })
+        );
+        let related = &result["relatedLocations"][0]["physicalLocation"];
+        assert_eq!(related["artifactLocation"], json!({ "uri": "env.schema.yml", "uriBaseId": "%SRCROOT%" }));
+        assert_eq!(related["region"], json!({ "startLine": 2, "startColumn": 3 }));
+
+        let levels: Vec<&str> = run["results"].as_array().unwrap().iter().map(|r| r["level"].as_str().unwrap()).collect();
+        assert_eq!(levels, ["error", "error", "warning", "note"]);
+    }
+
+    #[test]
+    fn junit_fails_only_cases_with_errors() {
+        let (_dir, report) = fixture("junit");
+        let xml = render_junit(&report);
+
+        assert!(xml.contains("<testsuites name=\"env-checker\" tests=\"4\" failures=\"2\">"));
+        assert!(xml.contains("<testsuite name=\"env\" tests=\"4\" failures=\"2\" errors=\"0\" skipped=\"0\">"));
+        assert!(xml.contains("<failure type=\"missing-env-var\" message=\"Missing required environment variable: &apos;DATABASE_URL&apos;\">"));
+        assert!(xml.contains("<testcase name=\"OLD_HOST\" classname=\"env-checker.env\">\n      <system-out>"));
+        assert!(xml.contains("<testcase name=\"LEGACY\" classname=\"env-checker.unused-env-var\">"));
+        assert!(!xml.contains("name=\"workspace\""));
+    }
+}
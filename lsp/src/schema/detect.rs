@@ -0,0 +1,139 @@
+use crate::types::SchemaSource;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// Extensions of files that may hold a Zod schema.
+pub const ZOD_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+/// Files larger than this are never schemas worth sniffing.
+const MAX_SNIFF_SIZE: u64 = 1024 * 1024;
+
+/// Stems of files importing `zod` that count as schemas even when they do
+/// not read the environment themselves.
+const SCHEMA_STEMS: &[&str] = &["env", "schema", "config"];
+
+/// First-line marker of files written by `generate code`, which import Zod
+/// or Pydantic but must not be read back as a second schema.
+const GENERATED_MARKER: &str = "Generated by env-checker";
+
+static SETTINGS_IMPORT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\bfrom\s+pydantic(?:_settings)?\s+import\s+(?:\([^)]*|[^\n]*)\bBaseSettings\b").unwrap()
+});
+static T3_ENV_IMPORT: LazyLock<Regex> = LazyLock::new(|| import_regex(r"@t3-oss/env-[\w-]+"));
+static ZOD_IMPORT: LazyLock<Regex> = LazyLock::new(|| import_regex("zod"));
+static READS_ENV: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bprocess\.env\b|\bimport\.meta\.env\b|\bcreateEnv\s*\(").unwrap());
+
+/// Matches an ES import, `require` or dynamic import of `module` or one of
+/// its subpaths.
+fn import_regex(module: &str) -> Regex {
+    Regex::new(&format!(
+        r#"(?:\bfrom\s*|\brequire\s*\(\s*|\bimport\s*\(\s*)["'](?:{})(?:/[\w./-]*)?["']"#,
+        module
+    ))
+    .unwrap()
+}
+
+type DetectionCache = HashMap<PathBuf, (SystemTime, Option<SchemaSource>)>;
+
+fn cache() -> &'static Mutex<DetectionCache> {
+    static CACHE: OnceLock<Mutex<DetectionCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Decides from its imports whether a file is a schema: TypeScript and
+/// JavaScript files importing `@t3-oss/env-*`, or importing `zod` and either
+/// reading the environment or named `env`, `schema` or `config`, and Python
+/// files importing `BaseSettings`.
+///
+/// Results are cached by modification time, so repeated discovery only
+/// reads files that changed.
+pub fn detect_schema(path: &Path) -> Option<SchemaSource> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_SNIFF_SIZE || !is_candidate(path) {
+        return None;
+    }
+    let modified = metadata.modified().ok()?;
+
+    if let Some((mtime, detected)) = cache().lock().ok()?.get(path) {
+        if *mtime == modified {
+            return detected.clone();
+        }
+    }
+
+    let detected = fs::read_to_string(path).ok().and_then(|content| sniff(path, &content));
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(path.to_path_buf(), (modified, detected.clone()));
+    }
+    detected
+}
+
+/// Whether a file's name allows it to be a Zod or Pydantic schema at all.
+pub fn is_candidate(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("py") => true,
+        Some(ext) => ZOD_EXTENSIONS.contains(&ext) && !name.ends_with(".d.ts"),
+        None => false,
+    }
+}
+
+fn sniff(path: &Path, content: &str) -> Option<SchemaSource> {
+    if content.lines().next().is_some_and(|line| line.contains(GENERATED_MARKER)) {
+        return None;
+    }
+
+    let file_path = path.to_string_lossy().to_string();
+
+    if path.extension().is_some_and(|e| e == "py") {
+        return SETTINGS_IMPORT
+            .is_match(content)
+            .then_some(SchemaSource::Pydantic { file_path });
+    }
+
+    // Form, API and RPC schemas import `zod` too; only files that read the
+    // environment or are named like an environment schema describe it.
+    let schema_stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .is_some_and(|stem| SCHEMA_STEMS.contains(&stem));
+    let is_schema = T3_ENV_IMPORT.is_match(content)
+        || (ZOD_IMPORT.is_match(content) && (schema_stem || READS_ENV.is_match(content)));
+    is_schema.then_some(SchemaSource::Zod { file_path })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_zod_schema(path: &str, content: &str) -> bool {
+        matches!(sniff(Path::new(path), content), Some(SchemaSource::Zod { .. }))
+    }
+
+    #[test]
+    fn detects_zod_files_reading_the_environment() {
+        let schema = "import { z } from \"zod\";\nexport const env = z.object({}).parse(process.env);\n";
+        assert!(is_zod_schema("src/settings.ts", schema));
+        assert!(is_zod_schema("src/env.mjs", "import { createEnv } from \"@t3-oss/env-nextjs\";\n"));
+    }
+
+    #[test]
+    fn detects_zod_files_named_like_a_schema() {
+        let schema = "import { z } from \"zod\";\nexport const schema = z.object({});\n";
+        assert!(is_zod_schema("src/schema.ts", schema));
+        assert!(is_zod_schema("env.js", schema));
+        assert!(is_zod_schema("config.ts", schema));
+        assert!(!is_zod_schema("src/forms/login.ts", schema));
+        assert!(!is_zod_schema("src/schema.ts", "export const schema = {};\n"));
+    }
+
+    #[test]
+    fn skips_generated_files() {
+        let generated = "// Generated by env-checker from env.schema.yml. Do not edit.\nimport { z } from \"zod\";\nprocess.env;\n";
+        assert!(!is_zod_schema("env.generated.ts", generated));
+    }
+}
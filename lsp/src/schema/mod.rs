@@ -1,18 +1,87 @@
-use crate::types::{EnvType, EnvVar, ParsedSchema, SchemaSource};
+use crate::secrets::is_secret_name;
+use crate::types::{EnvType, EnvVar, ParsedSchema, Rules, SchemaLocation, SchemaSource};
 use anyhow::Result;
+use regex::Regex;
+use std::collections::HashSet;
+use tracing::warn;
 
+pub mod detect;
 pub mod zod;
 pub mod pydantic;
 pub mod yaml;
 
 pub fn parse_schema(source: &SchemaSource) -> Result<Option<ParsedSchema>> {
-    match source {
+    let parsed = match source {
         SchemaSource::Zod { file_path } => zod::parse_zod_schema(file_path),
         SchemaSource::Pydantic { file_path } => pydantic::parse_pydantic_schema(file_path),
         SchemaSource::Yaml { file_path } => yaml::parse_yaml_schema(file_path),
+    }?;
+
+    // YAML schemas decide for themselves, so `secret: false` can opt out.
+    if matches!(source, SchemaSource::Yaml { .. }) {
+        return Ok(parsed);
+    }
+
+    Ok(parsed.map(|mut schema| {
+        for var in &mut schema.variables {
+            var.secret = is_secret_name(&var.name);
+        }
+        schema
+    }))
+}
+
+/// Parses every schema source. Sources that fail to parse are logged and
+/// skipped, sources without variables are left out.
+pub fn parse_schemas(sources: &[SchemaSource]) -> Vec<ParsedSchema> {
+    sources.iter().filter_map(parse_schema_or_warn).collect()
+}
+
+/// Parses a single schema source, logging and skipping it on failure.
+pub fn parse_schema_or_warn(source: &SchemaSource) -> Option<ParsedSchema> {
+    match parse_schema(source) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            warn!("Failed to parse schema {:?}: {:?}", source, e);
+            None
+        }
     }
 }
 
+/// Merges the variables of several schemas.
+///
+/// When several schemas declare the same variable, the first one wins; see
+/// `drift::find_conflicts` for reporting where they disagree.
+pub fn merge_schemas(schemas: &[ParsedSchema]) -> Vec<EnvVar> {
+    let mut all_vars = Vec::new();
+    let mut seen_names = HashSet::new();
+
+    for var in schemas.iter().flat_map(|s| &s.variables) {
+        if seen_names.insert(var.name.clone()) {
+            all_vars.push(var.clone());
+        }
+    }
+
+    all_vars
+}
+
+/// Combines the rules of every schema.
+pub fn merge_rules(schemas: &[ParsedSchema]) -> Rules {
+    let mut rules = Rules::default();
+    for schema in schemas {
+        rules.extend(&schema.rules);
+    }
+    rules
+}
+
+/// Parses every schema source and merges their variables.
+pub fn load_schemas(sources: &[SchemaSource]) -> Vec<EnvVar> {
+    merge_schemas(&parse_schemas(sources))
+}
+
+/// Lower-cased names of URL types: `url` in YAML and Zod, and Pydantic's
+/// URL classes.
+const URL_TYPE_NAMES: &[&str] = &["url", "anyurl", "anyhttpurl", "httpurl", "fileurl", "ftpurl", "websocketurl", "anywebsocketurl"];
+
 fn map_type_string(type_str: &str) -> EnvType {
     let type_str = type_str.to_lowercase();
     
@@ -24,7 +93,69 @@ fn map_type_string(type_str: &str) -> EnvType {
         } else {
             EnvType::Number
         }
+    } else if type_str.contains("float") || type_str.contains("decimal") {
+        EnvType::Number
+    } else if type_str
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .any(|name| URL_TYPE_NAMES.contains(&name))
+    {
+        EnvType::Url
     } else {
         EnvType::String
     }
 }
+
+/// Extracts the replacement variable from a deprecation notice such as
+/// "use DATABASE_URL instead" or "renamed to DATABASE_URL".
+fn deprecation_replacement(message: &str) -> Option<String> {
+    let replacement_regex = Regex::new(r"(?i)\b(?:use|renamed to|replaced by)\s+`?([A-Za-z_][A-Za-z0-9_]*)`?").unwrap();
+
+    replacement_regex
+        .captures(message)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+/// Finds the first declaration of `name` in `content`, where a declaration is
+/// the name followed by a colon. With `indented`, the name must be indented
+/// (class attributes, YAML mapping entries). Matching is case-insensitive
+/// because parsers normalize names to upper case.
+fn locate_declaration(content: &str, name: &str, indented: bool, source: &SchemaSource) -> Option<SchemaLocation> {
+    let prefix = if indented { r"(?mi)^[ \t]+" } else { r"(?i)\b" };
+    let regex = Regex::new(&format!(r#"{}["']?({})["']?\s*:"#, prefix, regex::escape(name))).ok()?;
+    let offset = regex.captures(content)?.get(1)?.start();
+
+    let before = &content[..offset];
+    let line = before.matches('\n').count();
+    let column = before.rfind('\n').map_or(offset, |nl| offset - nl - 1);
+
+    Some(SchemaLocation {
+        source: source.clone(),
+        line,
+        column,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_type_names_of_every_format() {
+        let cases = [
+            ("boolean", EnvType::Boolean),
+            ("int", EnvType::Integer),
+            ("number", EnvType::Number),
+            ("float", EnvType::Number),
+            ("Decimal", EnvType::Number),
+            ("Optional[Decimal]", EnvType::Number),
+            ("HttpUrl", EnvType::Url),
+            ("url", EnvType::Url),
+            ("CurlOptions", EnvType::String),
+            ("str", EnvType::String),
+        ];
+        for (type_str, expected) in cases {
+            assert_eq!(map_type_string(type_str), expected, "{}", type_str);
+        }
+    }
+}
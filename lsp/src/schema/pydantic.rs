@@ -1,5 +1,5 @@
-use crate::types::{EnvType, EnvVar, ParsedSchema, SchemaSource};
-use crate::schema::map_type_string;
+use crate::types::{EnvVar, ParsedSchema, Rules, SchemaSource};
+use crate::schema::{deprecation_replacement, locate_declaration, map_type_string};
 use anyhow::{Context, Result};
 use regex::Regex;
 use std::fs;
@@ -15,12 +15,15 @@ pub fn parse_pydantic_schema(file_path: &str) -> Result<Option<ParsedSchema>> {
         return Ok(None);
     }
 
-    Ok(Some(ParsedSchema {
-        source: SchemaSource::Pydantic {
-            file_path: file_path.to_string(),
-        },
-        variables,
-    }))
+    let source = SchemaSource::Pydantic {
+        file_path: file_path.to_string(),
+    };
+
+    for var in &mut variables {
+        var.location = locate_declaration(&content, &var.name, true, &source);
+    }
+
+    Ok(Some(ParsedSchema { source, variables, rules: Rules::default() }))
 }
 
 fn find_class_definitions(content: &str, variables: &mut Vec<EnvVar>) {
@@ -37,6 +40,9 @@ fn parse_class_body(body: &str, variables: &mut Vec<EnvVar>) {
     let assignment_regex = Regex::new(r"(\w+)\s*:\s*([^\n=]+)(?:\s*=\s*([^\n]+))?").unwrap();
     
     for line in body.lines() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
         if let Some(captures) = assignment_regex.captures(line) {
             if let (Some(name), Some(type_hint)) = (captures.get(1), captures.get(2)) {
                 let value = captures.get(3).map_or("", |v| v.as_str());
@@ -56,6 +62,7 @@ fn parse_field_assignment(name: &str, type_hint: &str, value: &str, variables: &
     let mut description = None;
     let mut default = None;
     let mut optional = false;
+    let mut deprecated = None;
 
     if type_hint.contains("Optional[") || type_hint.contains(" | None") || type_hint.contains("NoneType") {
         optional = true;
@@ -63,6 +70,11 @@ fn parse_field_assignment(name: &str, type_hint: &str, value: &str, variables: &
 
     if value.contains("Field(") {
         parse_field_args(value, &mut description, &mut default, &mut optional);
+        deprecated = extract_deprecated(value);
+    } else if value.trim() == "None" {
+        optional = true;
+    } else if !value.trim().is_empty() {
+        default = Some(sanitize_python_value(value));
     }
 
     if default.is_some() {
@@ -76,6 +88,9 @@ fn parse_field_assignment(name: &str, type_hint: &str, value: &str, variables: &
         default,
         optional,
         group: None,
+        replaced_by: deprecated.as_deref().and_then(deprecation_replacement),
+        deprecated,
+        ..Default::default()
     });
 }
 
@@ -96,12 +111,23 @@ fn parse_field_args(value: &str, description: &mut Option<String>, default: &mut
     }
 }
 
+fn extract_deprecated(value: &str) -> Option<String> {
+    let deprecated_regex = Regex::new(r#"deprecated\s*=\s*(?:["']([^"']*)["']|(True))"#).unwrap();
+
+    let captures = deprecated_regex.captures(value)?;
+    match (captures.get(1), captures.get(2)) {
+        (Some(message), _) => Some(message.as_str().to_string()),
+        (None, Some(_)) => Some(String::new()),
+        _ => None,
+    }
+}
+
 fn sanitize_python_value(value: &str) -> String {
     let value = value.trim();
     
-    if value.starts_with('"') && value.ends_with('"') {
-        value[1..value.len()-1].to_string()
-    } else if value.starts_with('\'') && value.ends_with('\'') {
+    if (value.starts_with('"') && value.ends_with('"'))
+        || (value.starts_with('\'') && value.ends_with('\''))
+    {
         value[1..value.len()-1].to_string()
     } else if value == "True" {
         "true".to_string()
@@ -1,18 +1,36 @@
-use crate::types::{EnvType, EnvVar, ParsedSchema, SchemaSource};
-use crate::schema::map_type_string;
-use anyhow::{Context, Result};
+use crate::secrets::is_secret_name;
+use crate::types::{EnvVar, ParsedSchema, Rules, SchemaSource};
+use crate::schema::{deprecation_replacement, locate_declaration, map_type_string};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Deserialize)]
 struct YamlSchema {
+    #[serde(default, alias = "include")]
+    extends: Vec<YamlInclude>,
     variables: Option<YamlVariables>,
+    #[serde(default)]
+    rules: Rules,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum YamlInclude {
+    Path(String),
+    Detailed {
+        path: String,
+        #[serde(default)]
+        overrides: HashMap<String, YamlOverride>,
+    },
 }
 
 #[derive(Debug, Deserialize)]
 struct YamlVariables {
     #[serde(flatten)]
-    vars: std::collections::HashMap<String, YamlVar>,
+    vars: HashMap<String, YamlVar>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,11 +40,48 @@ struct YamlVar {
     #[serde(default)]
     description: Option<String>,
     #[serde(default)]
-    default: Option<String>,
+    default: Option<serde_yaml::Value>,
     #[serde(default = "default_false")]
     required: bool,
     #[serde(default)]
+    required_in: Vec<String>,
+    #[serde(default)]
     group: Option<String>,
+    #[serde(default)]
+    example: Option<serde_yaml::Value>,
+    #[serde(default, rename = "enum")]
+    allowed_values: Vec<serde_yaml::Value>,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    min: Option<f64>,
+    #[serde(default)]
+    max: Option<f64>,
+    /// Guessed from the variable name when not given.
+    #[serde(default)]
+    secret: Option<bool>,
+    #[serde(default)]
+    deprecated: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct YamlOverride {
+    #[serde(default, rename = "type")]
+    var_type: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    default: Option<serde_yaml::Value>,
+    #[serde(default)]
+    required: Option<bool>,
+    #[serde(default)]
+    required_in: Option<Vec<String>>,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    secret: Option<bool>,
+    #[serde(default)]
+    deprecated: Option<String>,
 }
 
 fn default_false() -> bool {
@@ -34,36 +89,187 @@ fn default_false() -> bool {
 }
 
 pub fn parse_yaml_schema(file_path: &str) -> Result<Option<ParsedSchema>> {
-    let content = fs::read_to_string(file_path)
-        .context(format!("Failed to read YAML file: {}", file_path))?;
+    let mut stack = Vec::new();
+    let (variables, rules) = load_yaml_variables(Path::new(file_path), &mut stack)?;
+
+    if variables.is_empty() && rules.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(ParsedSchema {
+        source: SchemaSource::Yaml {
+            file_path: file_path.to_string(),
+        },
+        variables,
+        rules,
+    }))
+}
+
+/// Loads the variables and rules of a YAML schema, resolving `extends`
+/// recursively.
+///
+/// Included files are resolved relative to the including file. Variables
+/// declared locally replace inherited ones with the same name, while rules
+/// of included files are kept alongside local ones. `stack` holds the
+/// canonical paths currently being loaded and is used to detect cycles.
+fn load_yaml_variables(file_path: &Path, stack: &mut Vec<PathBuf>) -> Result<(Vec<EnvVar>, Rules)> {
+    let canonical = fs::canonicalize(file_path)
+        .context(format!("Failed to resolve YAML schema: {}", file_path.display()))?;
+
+    if let Some(pos) = stack.iter().position(|p| p == &canonical) {
+        let cycle: Vec<_> = stack[pos..]
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        bail!("Cyclic schema include: {}", cycle.join(" -> "));
+    }
+
+    let content = fs::read_to_string(&canonical)
+        .context(format!("Failed to read YAML file: {}", file_path.display()))?;
 
     let yaml: YamlSchema = serde_yaml::from_str(&content)
         .context("Failed to parse YAML schema")?;
 
-    let variables = if let Some(vars_map) = yaml.variables {
-        vars_map.vars
+    stack.push(canonical.clone());
+
+    let base_dir = canonical.parent().unwrap_or(Path::new("."));
+    let mut variables: Vec<EnvVar> = Vec::new();
+    let mut rules = Rules::default();
+
+    for include in yaml.extends {
+        let (path, overrides) = match include {
+            YamlInclude::Path(path) => (path, HashMap::new()),
+            YamlInclude::Detailed { path, overrides } => (
+                path,
+                overrides.into_iter().map(|(k, v)| (k.to_uppercase(), v)).collect(),
+            ),
+        };
+
+        let (inherited, inherited_rules) = load_yaml_variables(&base_dir.join(&path), stack)
+            .context(format!("Failed to include schema '{}' from {}", path, file_path.display()))?;
+        rules.extend(&inherited_rules);
+
+        for mut var in inherited {
+            if let Some(over) = overrides.get(&var.name) {
+                apply_override(&mut var, over);
+            }
+            upsert_variable(&mut variables, var);
+        }
+    }
+
+    stack.pop();
+
+    let source = SchemaSource::Yaml {
+        file_path: canonical.to_string_lossy().to_string(),
+    };
+
+    if let Some(vars_map) = yaml.variables {
+        let mut declared: Vec<_> = vars_map
+            .vars
             .into_iter()
-            .map(|(name, var)| EnvVar {
+            .map(|(name, var)| {
+                let location = locate_declaration(&content, &name, true, &source);
+                (name, var, location)
+            })
+            .collect();
+        // Keep the order of the file rather than the map's.
+        declared.sort_by_key(|(_, _, location)| location.as_ref().map(|l| l.line));
+
+        for (name, var, location) in declared {
+            upsert_variable(&mut variables, EnvVar {
                 name: name.to_uppercase(),
                 var_type: map_type_string(&var.var_type),
                 description: var.description,
-                default: var.default,
+                default: var.default.as_ref().and_then(yaml_value_to_string),
                 optional: !var.required,
+                required_in: var.required_in,
                 group: var.group,
+                example: var.example.as_ref().and_then(yaml_value_to_string),
+                allowed_values: var.allowed_values.iter().filter_map(yaml_value_to_string).collect(),
+                pattern: var.pattern,
+                min: var.min,
+                max: var.max,
+                secret: var.secret.unwrap_or_else(|| is_secret_name(&name)),
+                replaced_by: var.deprecated.as_deref().and_then(deprecation_replacement),
+                deprecated: var.deprecated,
+                location,
+            });
+        }
+    }
+
+    rules.extend(&uppercase_rule_names(yaml.rules));
+
+    Ok((variables, rules))
+}
+
+/// Variable names in YAML schemas are case-insensitive, so rule names are
+/// uppercased like the declarations they refer to. Values are kept as is.
+fn uppercase_rule_names(rules: Rules) -> Rules {
+    let upper = |names: Vec<String>| names.into_iter().map(|n| n.to_uppercase()).collect::<Vec<_>>();
+
+    Rules {
+        requires: rules
+            .requires
+            .into_iter()
+            .map(|(trigger, required)| (trigger.to_uppercase(), upper(required)))
+            .collect(),
+        requires_if: rules
+            .requires_if
+            .into_iter()
+            .map(|(trigger, by_value)| {
+                let by_value = by_value.into_iter().map(|(value, required)| (value, upper(required))).collect();
+                (trigger.to_uppercase(), by_value)
             })
-            .collect()
-    } else {
-        Vec::new()
-    };
+            .collect(),
+        one_of: rules.one_of.into_iter().map(upper).collect(),
+        mutually_exclusive: rules.mutually_exclusive.into_iter().map(upper).collect(),
+    }
+}
 
-    if variables.is_empty() {
-        return Ok(None);
+fn apply_override(var: &mut EnvVar, over: &YamlOverride) {
+    if let Some(var_type) = &over.var_type {
+        var.var_type = map_type_string(var_type);
+    }
+    if let Some(description) = &over.description {
+        var.description = Some(description.clone());
+    }
+    if let Some(default) = &over.default {
+        var.default = yaml_value_to_string(default);
+    }
+    if let Some(required) = over.required {
+        var.optional = !required;
+    }
+    if let Some(required_in) = &over.required_in {
+        var.required_in = required_in.clone();
     }
+    if let Some(group) = &over.group {
+        var.group = Some(group.clone());
+    }
+    if let Some(secret) = over.secret {
+        var.secret = secret;
+    }
+    if let Some(deprecated) = &over.deprecated {
+        var.replaced_by = deprecation_replacement(deprecated);
+        var.deprecated = Some(deprecated.clone());
+    }
+}
 
-    Ok(Some(ParsedSchema {
-        source: SchemaSource::Yaml {
-            file_path: file_path.to_string(),
-        },
-        variables,
-    }))
+/// Renders a scalar YAML value the way it would be written in a `.env` file.
+fn yaml_value_to_string(value: &serde_yaml::Value) -> Option<String> {
+    match value {
+        serde_yaml::Value::Null => None,
+        serde_yaml::Value::Bool(b) => Some(b.to_string()),
+        serde_yaml::Value::Number(n) => Some(n.to_string()),
+        serde_yaml::Value::String(s) => Some(s.clone()),
+        other => serde_yaml::to_string(other).ok().map(|s| s.trim_end().to_string()),
+    }
+}
+
+fn upsert_variable(variables: &mut Vec<EnvVar>, var: EnvVar) {
+    if let Some(existing) = variables.iter_mut().find(|v| v.name == var.name) {
+        *existing = var;
+    } else {
+        variables.push(var);
+    }
 }
@@ -1,5 +1,5 @@
-use crate::types::{EnvType, EnvVar, ParsedSchema, SchemaSource};
-use crate::schema::map_type_string;
+use crate::types::{EnvType, EnvVar, ParsedSchema, Rules, SchemaSource};
+use crate::schema::{deprecation_replacement, locate_declaration, map_type_string};
 use anyhow::{Context, Result};
 use regex::Regex;
 use std::fs;
@@ -15,42 +15,186 @@ pub fn parse_zod_schema(file_path: &str) -> Result<Option<ParsedSchema>> {
         return Ok(None);
     }
 
-    Ok(Some(ParsedSchema {
-        source: SchemaSource::Zod {
-            file_path: file_path.to_string(),
-        },
-        variables,
-    }))
+    let source = SchemaSource::Zod {
+        file_path: file_path.to_string(),
+    };
+
+    for var in &mut variables {
+        var.location = locate_declaration(&content, &var.name, false, &source);
+    }
+
+    Ok(Some(ParsedSchema { source, variables, rules: Rules::default() }))
 }
 
+/// Parses `z.object({...})` bodies, and with `@t3-oss/env-*` the `server`,
+/// `client` and `shared` objects passed to `createEnv`.
 fn find_zod_object_definitions(content: &str, variables: &mut Vec<EnvVar>) {
-    let object_regex = Regex::new(r"z\.object\s*\(\s*\{([^}]+)\}\s*\)").unwrap();
-    
-    for captures in object_regex.captures_iter(content) {
-        if let Some(properties) = captures.get(1) {
-            parse_object_properties(properties.as_str(), variables);
+    let object_regex = if content.contains("createEnv(") {
+        Regex::new(r"\.object\s*\(\s*\{|\b(?:server|client|shared)\s*:\s*\{").unwrap()
+    } else {
+        Regex::new(r"\.object\s*\(\s*\{").unwrap()
+    };
+
+    for m in object_regex.find_iter(content) {
+        if let Some(properties) = matching_brace_body(&content[m.end()..]) {
+            parse_object_properties(properties, variables);
         }
     }
+}
+
+/// Yields the characters of `text` that are outside string literals and
+/// comments, together with the bracket depth at that point.
+fn code_chars(text: &str) -> Vec<(usize, char, i32)> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut in_comment = false;
+    let mut in_block_comment = false;
+    let mut previous = '\0';
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if in_comment {
+            in_comment = c != '\n';
+            continue;
+        }
+        if in_block_comment {
+            in_block_comment = !(previous == '*' && c == '/');
+            previous = if in_block_comment { c } else { '\0' };
+            continue;
+        }
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
 
-    let object_alt_regex = Regex::new(r"\.object\s*\(\s*\{([^}]+)\}\s*\)").unwrap();
-    for captures in object_alt_regex.captures_iter(content) {
-        if let Some(properties) = captures.get(1) {
-            parse_object_properties(properties.as_str(), variables);
+        match c {
+            '"' | '\'' | '`' => quote = Some(c),
+            '/' if chars.peek().is_some_and(|(_, next)| *next == '/') => in_comment = true,
+            '/' if chars.peek().is_some_and(|(_, next)| *next == '*') => {
+                chars.next();
+                in_block_comment = true;
+            }
+            '(' | '[' | '{' => {
+                result.push((i, c, depth));
+                depth += 1;
+            }
+            ')' | ']' | '}' => {
+                depth -= 1;
+                result.push((i, c, depth));
+            }
+            _ => result.push((i, c, depth)),
         }
     }
+
+    result
+}
+
+/// Returns the text up to the `}` closing an object literal whose opening
+/// brace has just been consumed.
+fn matching_brace_body(rest: &str) -> Option<&str> {
+    code_chars(rest)
+        .into_iter()
+        .find(|(_, c, depth)| *c == '}' && *depth < 0)
+        .map(|(i, _, _)| &rest[..i])
+}
+
+/// Splits an object literal body at its top-level commas, so property
+/// chains spanning several lines or containing nested objects stay intact.
+fn split_top_level(body: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+
+    for (i, c, depth) in code_chars(body) {
+        if c == ',' && depth == 0 {
+            parts.push(&body[start..i]);
+            start = i + 1;
+        }
+    }
+
+    parts.push(&body[start..]);
+    parts
 }
 
 fn parse_object_properties(properties: &str, variables: &mut Vec<EnvVar>) {
-    let property_regex = Regex::new(r"([A-Za-z_][A-Za-z0-9_]*)\s*:\s*([^,\n]+(?:,[^,\n]*)*)").unwrap();
-    
-    for captures in property_regex.captures_iter(properties) {
-        if let (Some(name), Some(value)) = (captures.get(1), captures.get(2)) {
-            parse_property_chain(name.as_str(), value.as_str(), variables);
+    let property_regex = Regex::new(r"^([A-Za-z_][A-Za-z0-9_]*)\s*:\s*([\s\S]+)$").unwrap();
+    let nested_object_regex = Regex::new(r"^z\s*\.\s*(?:object|strictObject|looseObject)\s*\(").unwrap();
+
+    for part in split_top_level(properties) {
+        let (code, doc) = strip_comments(part);
+
+        // Fields of nested objects are read as variables of their own.
+        if let Some(captures) = property_regex.captures(code.trim()) {
+            if !nested_object_regex.is_match(captures[2].trim()) {
+                parse_property_chain(&captures[1], &captures[2], &doc, variables);
+            }
         }
     }
 }
 
-fn parse_property_chain(name: &str, value: &str, variables: &mut Vec<EnvVar>) {
+/// Separates the code of a property from its comments, returning the code
+/// and the text of its `/** ... */` doc comments.
+fn strip_comments(text: &str) -> (String, String) {
+    let mut code = String::new();
+    let mut doc = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == q {
+                quote = None;
+            }
+            code.push(c);
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                while chars.next_if(|next| *next != '\n').is_some() {}
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let is_doc = chars.next_if_eq(&'*').is_some();
+                let mut comment = String::new();
+                while let Some(next) = chars.next() {
+                    if next == '*' && chars.next_if_eq(&'/').is_some() {
+                        break;
+                    }
+                    comment.push(next);
+                }
+                if is_doc {
+                    for line in comment.lines() {
+                        doc.push_str(line.trim().trim_start_matches('*').trim());
+                        doc.push('\n');
+                    }
+                }
+            }
+            _ => {
+                if matches!(c, '"' | '\'' | '`') {
+                    quote = Some(c);
+                }
+                code.push(c);
+            }
+        }
+    }
+
+    (code, doc)
+}
+
+fn parse_property_chain(name: &str, value: &str, doc: &str, variables: &mut Vec<EnvVar>) {
     let mut var_type = EnvType::String;
     let mut description = None;
     let mut default = None;
@@ -61,13 +205,39 @@ fn parse_property_chain(name: &str, value: &str, variables: &mut Vec<EnvVar>) {
 
     parse_type_chain(value, &mut var_type, &mut description, &mut default, &mut optional, &mut group);
 
+    let mut required_in = match description.take() {
+        Some(desc) => {
+            let (rest, profiles) = extract_required_in(&desc);
+            description = Some(rest).filter(|d| !d.is_empty());
+            profiles
+        }
+        None => Vec::new(),
+    };
+    if required_in.is_empty() {
+        required_in = extract_required_in(doc).1;
+    }
+
+    let mut deprecated = description
+        .as_deref()
+        .and_then(|d| d.strip_prefix("@deprecated"))
+        .map(|rest| rest.trim().to_string());
+    if deprecated.is_some() {
+        description = None;
+    } else {
+        deprecated = extract_deprecated_tag(doc);
+    }
+
     variables.push(EnvVar {
         name: name.to_string(),
         var_type,
         description,
         default,
         optional,
+        required_in,
         group,
+        replaced_by: deprecated.as_deref().and_then(deprecation_replacement),
+        deprecated,
+        ..Default::default()
     });
 }
 
@@ -93,8 +263,10 @@ fn parse_type_chain(
         *description = Some(desc);
     }
 
+    // Zod fills in defaults, so such variables may be left unset.
     if let Some(def) = extract_default(value) {
         *default = Some(def);
+        *optional = true;
     }
 
     if let Some(grp) = extract_group(value) {
@@ -103,6 +275,16 @@ fn parse_type_chain(
 }
 
 fn extract_type_name(value: &str) -> Option<&str> {
+    if value.contains("z.url(") || value.contains(".url(") {
+        return Some("url");
+    }
+    if value.contains("z.stringbool(") {
+        return Some("boolean");
+    }
+    if value.contains(".int(") {
+        return Some("int");
+    }
+
     let types = ["z.string", "z.number", "z.boolean", "z.int", "z.float"];
     
     for type_name in &types {
@@ -125,24 +307,44 @@ fn extract_type_name(value: &str) -> Option<&str> {
 }
 
 fn extract_description(value: &str) -> Option<String> {
-    let describe_regex = Regex::new(r#"\.describe\s*\(\s*['"]([^'"]*)['"]\s*\)"#).unwrap();
-    
-    if let Some(captures) = describe_regex.captures(value) {
-        if let Some(desc) = captures.get(1) {
-            return Some(desc.as_str().to_string());
-        }
-    }
+    let describe_regex = Regex::new(r#"\.describe\s*\(\s*(?:"([^"]*)"|'([^']*)'|`([^`]*)`)\s*\)"#).unwrap();
 
-    None
+    describe_regex
+        .captures(value)
+        .and_then(|captures| captures.iter().skip(1).flatten().next())
+        .map(|desc| desc.as_str().to_string())
+}
+
+/// Reads the message of a `@deprecated` tag in a doc comment.
+fn extract_deprecated_tag(doc: &str) -> Option<String> {
+    let tag_regex = Regex::new(r"@deprecated\b([^@]*)").unwrap();
+
+    tag_regex
+        .captures(doc)
+        .map(|captures| captures[1].split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Splits a `@requiredIn production,staging` tag off a description, returning
+/// the remaining description and the listed profiles.
+fn extract_required_in(description: &str) -> (String, Vec<String>) {
+    let tag_regex = Regex::new(r"@requiredIn\s+([\w-]+(?:\s*,\s*[\w-]+)*)").unwrap();
+
+    let Some(captures) = tag_regex.captures(description) else {
+        return (description.to_string(), Vec::new());
+    };
+    let profiles = captures[1].split(',').map(|p| p.trim().to_string()).collect();
+    let rest = tag_regex.replace(description, "");
+
+    (rest.split_whitespace().collect::<Vec<_>>().join(" "), profiles)
 }
 
 fn extract_default(value: &str) -> Option<String> {
-    let default_regex = Regex::new(r"\.default\s*\(\s*([^)]+)\s*\)").unwrap();
+    let default_regex = Regex::new(r#"\.default\s*\(\s*("[^"]*"|'[^']*'|`[^`]*`|[^)]+)\s*\)"#).unwrap();
     
     if let Some(captures) = default_regex.captures(value) {
         if let Some(def) = captures.get(1) {
             let def = def.as_str().trim();
-            if def.starts_with('"') && def.ends_with('"') {
+            if def.len() >= 2 && def.starts_with(['"', '\'', '`']) && def.ends_with(&def[..1]) {
                 return Some(def[1..def.len()-1].to_string());
             } else if def == "false" {
                 return Some("false".to_string());
@@ -168,3 +370,136 @@ fn extract_group(value: &str) -> Option<String> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Vec<EnvVar> {
+        let mut variables = Vec::new();
+        find_zod_object_definitions(content, &mut variables);
+        variables
+    }
+
+    fn names(variables: &[EnvVar]) -> Vec<&str> {
+        variables.iter().map(|v| v.name.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_fields_of_nested_objects_but_not_the_objects() {
+        let vars = parse(
+            r#"export const envSchema = z.object({
+    database: z.object({
+        DATABASE_HOST: z.string(),
+        DATABASE_USER: z.string().default("app"),
+    }),
+    PORT: z.coerce.number().int(),
+});"#,
+        );
+
+        assert_eq!(names(&vars), ["PORT", "DATABASE_HOST", "DATABASE_USER"]);
+        assert_eq!(vars[0].var_type, EnvType::Integer);
+        assert_eq!(vars[2].default.as_deref(), Some("app"));
+    }
+
+    #[test]
+    fn keeps_chains_with_objects_intact() {
+        let vars = parse(
+            r#"export const envSchema = z.object({
+    API_KEY: z.string()
+        .describe("Key for the API")
+        .register(envRegistry, { envType: "runtime", group: "Secrets" }),
+    DEBUG: z.boolean().default(false),
+});"#,
+        );
+
+        assert_eq!(names(&vars), ["API_KEY", "DEBUG"]);
+        assert_eq!(vars[0].description.as_deref(), Some("Key for the API"));
+        assert_eq!(vars[0].group.as_deref(), Some("Secrets"));
+        assert_eq!(vars[1].var_type, EnvType::Boolean);
+    }
+
+    #[test]
+    fn ignores_braces_and_commas_in_comments_and_strings() {
+        let vars = parse(
+            r#"export const envSchema = z.object({
+    // OLD_PORT: z.string(), { was removed
+    /* TIMEOUT: z.number() }, */
+    TEMPLATE: z.string().default("{name}, }"),
+    API_URL: z.url().describe('Base URL, e.g. {host}/api // not a comment'),
+    GREETING: z.string().describe("Don't panic"), // trailing comment, {
+    LOG_LEVEL: z.enum(["debug", "info"]).default('info'),
+});"#,
+        );
+
+        assert_eq!(names(&vars), ["TEMPLATE", "API_URL", "GREETING", "LOG_LEVEL"]);
+        assert_eq!(vars[0].default.as_deref(), Some("{name}, }"));
+        assert_eq!(vars[1].var_type, EnvType::Url);
+        assert_eq!(vars[1].description.as_deref(), Some("Base URL, e.g. {host}/api // not a comment"));
+        assert_eq!(vars[2].description.as_deref(), Some("Don't panic"));
+        assert_eq!(vars[3].default.as_deref(), Some("info"));
+    }
+
+    #[test]
+    fn reads_chained_modifiers_in_any_order() {
+        let vars = parse(
+            r#"export const envSchema = z.object({
+    PORT: z.coerce.number().int().min(1).default(3000).describe("Port to listen on"),
+    SENTRY_DSN: z.string().url().optional(),
+    RATE: z.coerce.number().nullable(),
+    NAME: z.string().min(1),
+});"#,
+        );
+
+        assert_eq!(names(&vars), ["PORT", "SENTRY_DSN", "RATE", "NAME"]);
+        assert_eq!(vars[0].var_type, EnvType::Integer);
+        assert_eq!(vars[0].default.as_deref(), Some("3000"));
+        assert_eq!(vars[0].description.as_deref(), Some("Port to listen on"));
+        assert!(vars[0].optional);
+        assert_eq!(vars[1].var_type, EnvType::Url);
+        assert!(vars[1].optional);
+        assert_eq!(vars[2].var_type, EnvType::Number);
+        assert!(vars[2].optional);
+        assert!(!vars[3].optional);
+    }
+
+    #[test]
+    fn reads_required_in_and_deprecated_from_descriptions() {
+        let vars = parse(
+            r#"export const envSchema = z.object({
+    SENTRY_DSN: z.string().optional().describe("Error reporting @requiredIn production, staging"),
+    DB_URL: z.string().optional().describe("@deprecated use DATABASE_URL instead"),
+});"#,
+        );
+
+        assert_eq!(vars[0].required_in, ["production", "staging"]);
+        assert_eq!(vars[0].description.as_deref(), Some("Error reporting"));
+        assert_eq!(vars[1].deprecated.as_deref(), Some("use DATABASE_URL instead"));
+        assert_eq!(vars[1].replaced_by.as_deref(), Some("DATABASE_URL"));
+        assert_eq!(vars[1].description, None);
+    }
+
+    #[test]
+    fn reads_required_in_and_deprecated_from_doc_comments() {
+        let vars = parse(
+            r#"export const envSchema = z.object({
+    /**
+     * Error reporting, don't set it locally.
+     * @requiredIn production
+     */
+    SENTRY_DSN: z.string().optional(),
+    /** @deprecated renamed to DATABASE_URL */
+    DB_URL: z.string().optional(),
+    DATABASE_URL: z.string(),
+});"#,
+        );
+
+        assert_eq!(names(&vars), ["SENTRY_DSN", "DB_URL", "DATABASE_URL"]);
+        assert_eq!(vars[0].required_in, ["production"]);
+        assert_eq!(vars[0].deprecated, None);
+        assert_eq!(vars[1].deprecated.as_deref(), Some("renamed to DATABASE_URL"));
+        assert_eq!(vars[1].replaced_by.as_deref(), Some("DATABASE_URL"));
+        assert!(vars[2].required_in.is_empty());
+        assert_eq!(vars[2].deprecated, None);
+    }
+}
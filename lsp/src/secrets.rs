@@ -0,0 +1,152 @@
+use crate::git;
+use crate::types::{EnvFileEntry, EnvVar};
+use std::path::Path;
+
+const SECRET_SUFFIXES: &[&str] = &[
+    "_TOKEN",
+    "_PASSWORD",
+    "_PASSWD",
+    "_PWD",
+    "_KEY",
+    "_SECRET",
+    "_CREDENTIALS",
+    "_DSN",
+];
+
+const SECRET_NAMES: &[&str] = &["TOKEN", "PASSWORD", "SECRET", "API_KEY", "PRIVATE_KEY"];
+
+const PLACEHOLDER_MARKERS: &[&str] = &[
+    "changeme",
+    "change-me",
+    "change_me",
+    "example",
+    "placeholder",
+    "your",
+    "xxx",
+    "todo",
+    "dummy",
+    "replace",
+    "secret",
+    "password",
+    "<",
+    "${",
+    "***",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeakReason {
+    /// The file is an example file meant to be committed.
+    ExampleFile,
+    /// The file is staged in the git index.
+    TrackedByGit,
+    /// The file is not covered by any `.gitignore` rule.
+    NotIgnored,
+}
+
+#[derive(Debug, Clone)]
+pub struct SecretLeak {
+    pub entry: EnvFileEntry,
+    pub reason: LeakReason,
+}
+
+/// Guesses from the variable name whether it holds a secret.
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_uppercase();
+
+    if name.contains("PUBLIC") {
+        return false;
+    }
+
+    SECRET_NAMES.contains(&name.as_str())
+        || SECRET_SUFFIXES.iter().any(|s| name.ends_with(s))
+        || name.contains("SECRET")
+}
+
+/// Returns true for values that look like actual credentials rather than
+/// placeholders such as `changeme` or `<your-token>`.
+pub fn looks_like_real_secret(value: &str) -> bool {
+    let value = value.trim();
+
+    if value.chars().count() < 8 || value.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    let lower = value.to_lowercase();
+    if PLACEHOLDER_MARKERS.iter().any(|m| lower.contains(m)) {
+        return false;
+    }
+
+    let first = value.chars().next().unwrap_or_default();
+    if value.chars().all(|c| c == first) {
+        return false;
+    }
+
+    let classes = [
+        value.chars().any(|c| c.is_ascii_lowercase()),
+        value.chars().any(|c| c.is_ascii_uppercase()),
+        value.chars().any(|c| c.is_ascii_digit()),
+        value.chars().any(|c| !c.is_ascii_alphanumeric()),
+    ];
+
+    classes.iter().filter(|c| **c).count() >= 2
+}
+
+pub fn mask_value(value: &str) -> String {
+    "•".repeat(value.chars().count().clamp(4, 12))
+}
+
+/// Finds secret values in `entries` that would end up in version control.
+///
+/// Only entries of `file_path` are considered; a variable counts as secret if
+/// its schema says so or its name looks like one.
+pub fn find_leaked_secrets(entries: &[EnvFileEntry], schema_vars: &[EnvVar], file_path: &Path) -> Vec<SecretLeak> {
+    let file_path_str = file_path.to_string_lossy();
+
+    let candidates: Vec<&EnvFileEntry> = entries
+        .iter()
+        .filter(|entry| entry.file_path == file_path_str)
+        .filter(|entry| {
+            schema_vars
+                .iter()
+                .find(|v| v.name == entry.name)
+                .map_or_else(|| is_secret_name(&entry.name), |v| v.secret)
+        })
+        .filter(|entry| entry.value.as_deref().is_some_and(looks_like_real_secret))
+        .collect();
+
+    // Only files holding secrets are worth asking git about.
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+    let reason = match leak_reason(file_path) {
+        Some(reason) => reason,
+        None => return Vec::new(),
+    };
+
+    candidates
+        .into_iter()
+        .map(|entry| SecretLeak {
+            entry: entry.clone(),
+            reason: reason.clone(),
+        })
+        .collect()
+}
+
+fn leak_reason(file_path: &Path) -> Option<LeakReason> {
+    let file_name = file_path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    if file_name.ends_with(".example") || file_name.ends_with(".sample") || file_name.ends_with(".template") {
+        return Some(LeakReason::ExampleFile);
+    }
+
+    let repo_root = git::find_repo_root(file_path)?;
+
+    if git::tracked_files(&repo_root).is_ok_and(|files| files.contains(file_path)) {
+        return Some(LeakReason::TrackedByGit);
+    }
+
+    if !git::is_ignored(&repo_root, file_path) {
+        return Some(LeakReason::NotIgnored);
+    }
+
+    None
+}
@@ -0,0 +1,243 @@
+use crate::env_file;
+use crate::infer;
+use crate::types::{EnvVar, Groups};
+use crate::workspace::Workspace;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tower_lsp::lsp_types::{DocumentSymbol, Location, Position, Range, SymbolInformation, SymbolKind, SymbolTag, Url};
+
+/// A namespace of the outline with the variables it holds.
+struct Section {
+    name: String,
+    detail: Option<String>,
+    header_line: Option<usize>,
+    children: Vec<DocumentSymbol>,
+}
+
+/// Builds the outline of an env file: sections as namespaces holding their
+/// variables, each shown with its type.
+///
+/// Sections come from comment headers, recognised the same way as when a
+/// schema is inferred from the file. A file without headers is divided by
+/// the schema groups of its variables instead, under their configured
+/// titles. A section spans consecutive variables only, so a group that is
+/// split across the file appears once per run and sections never overlap.
+/// Variables outside any section are listed at the top level.
+pub fn document_symbols(content: &str, schema_vars: &[EnvVar], groups: &Groups) -> Vec<DocumentSymbol> {
+    let lines: Vec<&str> = content.lines().collect();
+    let entries = env_file::parse_env_content(content, Path::new(""));
+
+    let headers: HashMap<String, String> = infer::infer_variables(content)
+        .into_iter()
+        .filter_map(|var| Some((var.name, var.group?)))
+        .collect();
+    let use_headers = !headers.is_empty();
+
+    let mut symbols = Vec::new();
+    let mut sections: Vec<Section> = Vec::new();
+    // Whether the previous variable was added to the last section.
+    let mut last_in_section = false;
+
+    for entry in &entries {
+        let schema_var = schema_vars.iter().find(|v| v.name == entry.name);
+        let var_type = match schema_var {
+            Some(var) => var.var_type.name(),
+            None => infer::infer_type(entry.value.as_deref().unwrap_or_default()).name(),
+        };
+
+        let line_end = utf16_len(lines.get(entry.line).copied().unwrap_or_default());
+        let name_start = Position::new(entry.line as u32, entry.column as u32);
+        let symbol = new_symbol(
+            entry.name.clone(),
+            Some(var_type.to_string()),
+            SymbolKind::VARIABLE,
+            Range::new(Position::new(entry.line as u32, 0), Position::new(entry.line as u32, line_end)),
+            Range::new(name_start, Position::new(entry.line as u32, (entry.column + entry.name.len()) as u32)),
+            schema_var.is_some_and(|v| v.deprecated.is_some()),
+        );
+
+        let section = if use_headers {
+            headers.get(&entry.name).map(|header| (header.clone(), None))
+        } else {
+            schema_var.and_then(|v| v.group.as_ref()).map(|group| {
+                let config = groups.get(group);
+                let title = config.and_then(|c| c.title()).unwrap_or(group).to_string();
+                (title, config.and_then(|c| c.description()).map(String::from))
+            })
+        };
+        let Some((name, detail)) = section else {
+            symbols.push(symbol);
+            last_in_section = false;
+            continue;
+        };
+
+        match sections.last_mut().filter(|s| last_in_section && s.name == name) {
+            Some(section) => section.children.push(symbol),
+            None => {
+                let header_line = use_headers.then(|| find_header_line(&lines, entry.line, &name)).flatten();
+                sections.push(Section {
+                    name,
+                    detail,
+                    header_line,
+                    children: vec![symbol],
+                });
+            }
+        }
+        last_in_section = true;
+    }
+
+    for section in sections {
+        let first = section.children.first().map_or(0, |c| c.range.start.line);
+        let last = section.children.last().map_or(first, |c| c.range.end.line);
+        let start_line = section.header_line.map_or(first, |line| line as u32);
+        let end = Position::new(last, utf16_len(lines.get(last as usize).copied().unwrap_or_default()));
+        let selection_end = match section.header_line {
+            Some(line) => Position::new(line as u32, utf16_len(lines[line])),
+            None => section.children[0].selection_range.end,
+        };
+
+        let mut namespace = new_symbol(
+            section.name,
+            section.detail,
+            SymbolKind::NAMESPACE,
+            Range::new(Position::new(start_line, 0), end),
+            Range::new(Position::new(start_line, 0), selection_end),
+            false,
+        );
+        namespace.children = Some(section.children);
+        symbols.push(namespace);
+    }
+
+    symbols.sort_by_key(|s| (s.range.start.line, s.range.start.character));
+    symbols
+}
+
+/// Flattens an outline for clients without hierarchical symbol support,
+/// naming each variable's section as its container.
+#[allow(deprecated)]
+pub fn flatten_symbols(uri: &Url, symbols: Vec<DocumentSymbol>, container: Option<&str>) -> Vec<SymbolInformation> {
+    let mut flat = Vec::new();
+
+    for symbol in symbols {
+        flat.push(SymbolInformation {
+            name: symbol.name.clone(),
+            kind: symbol.kind,
+            tags: symbol.tags.clone(),
+            deprecated: None,
+            location: Location::new(uri.clone(), symbol.range),
+            container_name: container.map(String::from),
+        });
+        if let Some(children) = symbol.children {
+            flat.extend(flatten_symbols(uri, children, Some(&symbol.name)));
+        }
+    }
+
+    flat
+}
+
+/// Finds schema declarations and env file assignments whose variable name
+/// contains `query`, ignoring case. An empty query lists everything.
+///
+/// Schema declarations are listed first, each under the path of its schema
+/// file, followed by every env file that sets a matching variable.
+#[allow(deprecated)]
+pub fn workspace_symbols(workspaces: &[Workspace], query: &str) -> Vec<SymbolInformation> {
+    let query = query.to_lowercase();
+    let matches = |name: &str| name.to_lowercase().contains(&query);
+
+    let mut seen = HashSet::new();
+    let mut declarations = Vec::new();
+    let mut assignments = Vec::new();
+
+    for workspace in workspaces {
+        for schema in workspace.schemas() {
+            for var in schema.variables.iter().filter(|v| matches(&v.name)) {
+                // Variables inherited through `extends` point at the file
+                // declaring them, so they are listed there only once.
+                let (file_path, line, column) = match &var.location {
+                    Some(location) => (location.source.file_path(), location.line, location.column),
+                    None => (schema.source.file_path(), 0, 0),
+                };
+                let Ok(uri) = Url::from_file_path(file_path) else { continue };
+                if !seen.insert((uri.clone(), line, var.name.clone())) {
+                    continue;
+                }
+
+                declarations.push(SymbolInformation {
+                    name: var.name.clone(),
+                    kind: SymbolKind::PROPERTY,
+                    tags: var.deprecated.is_some().then(|| vec![SymbolTag::DEPRECATED]),
+                    deprecated: None,
+                    location: Location::new(uri, name_range(line, column, &var.name)),
+                    container_name: Some(relative_path(file_path, &workspace.root)),
+                });
+            }
+        }
+
+        for entries in workspace.env_files.values() {
+            for entry in entries.iter().filter(|e| matches(&e.name)) {
+                let Ok(uri) = Url::from_file_path(&entry.file_path) else { continue };
+                if !seen.insert((uri.clone(), entry.line, entry.name.clone())) {
+                    continue;
+                }
+
+                assignments.push(SymbolInformation {
+                    name: entry.name.clone(),
+                    kind: SymbolKind::VARIABLE,
+                    tags: None,
+                    deprecated: None,
+                    location: Location::new(uri, name_range(entry.line, entry.column, &entry.name)),
+                    container_name: Some(relative_path(&entry.file_path, &workspace.root)),
+                });
+            }
+        }
+    }
+
+    let order = |s: &SymbolInformation| (s.name.clone(), s.location.uri.to_string(), s.location.range.start.line);
+    declarations.sort_by_key(order);
+    assignments.sort_by_key(order);
+    declarations.extend(assignments);
+    declarations
+}
+
+fn name_range(line: usize, column: usize, name: &str) -> Range {
+    Range::new(
+        Position::new(line as u32, column as u32),
+        Position::new(line as u32, (column + name.len()) as u32),
+    )
+}
+
+fn relative_path(file_path: &str, root: &Path) -> String {
+    let path = Path::new(file_path);
+    path.strip_prefix(root).unwrap_or(path).to_string_lossy().to_string()
+}
+
+#[allow(deprecated)]
+fn new_symbol(
+    name: String,
+    detail: Option<String>,
+    kind: SymbolKind,
+    range: Range,
+    selection_range: Range,
+    deprecated: bool,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        tags: deprecated.then(|| vec![SymbolTag::DEPRECATED]),
+        deprecated: None,
+        range,
+        selection_range,
+        children: None,
+    }
+}
+
+/// The comment line above `line` whose text is the section header.
+fn find_header_line(lines: &[&str], line: usize, header: &str) -> Option<usize> {
+    (0..line).rev().find(|&i| infer::comment_text(lines[i]).as_deref() == Some(header))
+}
+
+fn utf16_len(line: &str) -> u32 {
+    line.chars().map(|c| c.len_utf16() as u32).sum()
+}
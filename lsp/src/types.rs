@@ -1,47 +1,359 @@
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum EnvType {
+    #[default]
     String,
     Boolean,
     Number,
     Integer,
+    Url,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl EnvType {
+    /// The type name used in YAML schemas and shown in hovers.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnvType::String => "string",
+            EnvType::Boolean => "boolean",
+            EnvType::Number => "number",
+            EnvType::Integer => "integer",
+            EnvType::Url => "url",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EnvVar {
     pub name: String,
     pub var_type: EnvType,
     pub description: Option<String>,
     pub default: Option<String>,
     pub optional: bool,
+    /// Profiles in which the variable is required even though it is
+    /// optional elsewhere.
+    pub required_in: Vec<String>,
     pub group: Option<String>,
+    pub example: Option<String>,
+    pub allowed_values: Vec<String>,
+    pub pattern: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub secret: bool,
+    pub deprecated: Option<String>,
+    pub replaced_by: Option<String>,
+    pub location: Option<SchemaLocation>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl EnvVar {
+    /// Whether the variable has to be set when validating `profile`: always
+    /// unless it is optional, and otherwise only in the profiles listed in
+    /// `required_in`.
+    pub fn is_required_in(&self, profile: Option<&str>) -> bool {
+        !self.optional || profile.is_some_and(|profile| self.required_in.iter().any(|p| p == profile))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaKind {
+    Zod,
+    Pydantic,
+    Yaml,
+}
+
+impl SchemaKind {
+    pub const ALL: [SchemaKind; 3] = [SchemaKind::Zod, SchemaKind::Pydantic, SchemaKind::Yaml];
+
+    /// Guesses the kind of a schema file from its extension.
+    pub fn from_extension(path: &std::path::Path) -> Option<Self> {
+        match path.extension().and_then(|e| e.to_str())? {
+            "ts" | "tsx" | "mts" | "cts" | "js" | "jsx" | "mjs" | "cjs" => Some(SchemaKind::Zod),
+            "py" => Some(SchemaKind::Pydantic),
+            "yml" | "yaml" => Some(SchemaKind::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaKind::Zod => "zod",
+            SchemaKind::Pydantic => "pydantic",
+            SchemaKind::Yaml => "yaml",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SchemaSource {
     Zod { file_path: String },
     Pydantic { file_path: String },
     Yaml { file_path: String },
 }
 
+impl SchemaSource {
+    pub fn file_path(&self) -> &str {
+        match self {
+            SchemaSource::Zod { file_path }
+            | SchemaSource::Pydantic { file_path }
+            | SchemaSource::Yaml { file_path } => file_path,
+        }
+    }
+
+    pub fn new(kind: SchemaKind, file_path: String) -> Self {
+        match kind {
+            SchemaKind::Zod => SchemaSource::Zod { file_path },
+            SchemaKind::Pydantic => SchemaSource::Pydantic { file_path },
+            SchemaKind::Yaml => SchemaSource::Yaml { file_path },
+        }
+    }
+
+    pub fn kind(&self) -> SchemaKind {
+        match self {
+            SchemaSource::Zod { .. } => SchemaKind::Zod,
+            SchemaSource::Pydantic { .. } => SchemaKind::Pydantic,
+            SchemaSource::Yaml { .. } => SchemaKind::Yaml,
+        }
+    }
+}
+
+/// Where a variable is declared in a schema file. Lines and columns are
+/// zero-based, like LSP positions.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SchemaLocation {
+    pub source: SchemaSource,
+    pub line: usize,
+    pub column: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct ParsedSchema {
     pub source: SchemaSource,
     pub variables: Vec<EnvVar>,
+    pub rules: Rules,
+}
+
+/// Requirements between variables, declared in YAML schemas and in the
+/// configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Rules {
+    /// Variables that have to be set whenever the key is set.
+    #[serde(default)]
+    pub requires: BTreeMap<String, Vec<String>>,
+    /// Variables that have to be set when the key has a given value, keyed by
+    /// that value.
+    #[serde(default, alias = "requiresIf")]
+    pub requires_if: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+    /// Sets of variables of which exactly one has to be set.
+    #[serde(default, alias = "oneOf")]
+    pub one_of: Vec<Vec<String>>,
+    /// Sets of variables of which at most one may be set.
+    #[serde(default, alias = "mutuallyExclusive")]
+    pub mutually_exclusive: Vec<Vec<String>>,
+}
+
+impl Rules {
+    /// Adds the rules of `other`, skipping sets that are already present.
+    pub fn extend(&mut self, other: &Rules) {
+        for (trigger, required) in &other.requires {
+            let entry = self.requires.entry(trigger.clone()).or_default();
+            for name in required {
+                if !entry.contains(name) {
+                    entry.push(name.clone());
+                }
+            }
+        }
+        for (trigger, by_value) in &other.requires_if {
+            for (value, required) in by_value {
+                let entry = self
+                    .requires_if
+                    .entry(trigger.clone())
+                    .or_default()
+                    .entry(value.clone())
+                    .or_default();
+                for name in required {
+                    if !entry.contains(name) {
+                        entry.push(name.clone());
+                    }
+                }
+            }
+        }
+        for set in &other.one_of {
+            if !self.one_of.contains(set) {
+                self.one_of.push(set.clone());
+            }
+        }
+        for set in &other.mutually_exclusive {
+            if !self.mutually_exclusive.contains(set) {
+                self.mutually_exclusive.push(set.clone());
+            }
+        }
+    }
+
+    /// Whether any rule refers to the variable `name`.
+    pub fn mentions(&self, name: &str) -> bool {
+        self.requires.iter().any(|(trigger, required)| trigger == name || required.iter().any(|r| r == name))
+            || self.requires_if.iter().any(|(trigger, by_value)| {
+                trigger == name || by_value.values().flatten().any(|r| r == name)
+            })
+            || self.one_of.iter().chain(&self.mutually_exclusive).flatten().any(|n| n == name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requires.is_empty()
+            && self.requires_if.is_empty()
+            && self.one_of.is_empty()
+            && self.mutually_exclusive.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default, alias = "schemaFiles")]
+    pub schema_files: Vec<SchemaFileEntry>,
+    #[serde(default, alias = "envFiles")]
+    pub env_files: Vec<String>,
+    #[serde(default = "default_true", alias = "autoDiscover")]
+    pub auto_discover: bool,
+    /// Display settings of variable groups, keyed by the group name used in
+    /// schemas. Configured groups are listed first, in this order.
     #[serde(default)]
-    pub schema_files: Vec<String>,
+    pub groups: Groups,
     #[serde(default)]
-    pub env_files: Vec<String>,
+    pub profiles: BTreeMap<String, Vec<String>>,
+    /// Overrides of whether variables are required, keyed by variable name.
+    #[serde(default)]
+    pub required: BTreeMap<String, RequiredOverride>,
+    /// Requirements between variables, in addition to those of the schemas.
+    #[serde(default)]
+    pub rules: Rules,
+    /// Globs, relative to the workspace root, left out of schema discovery.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+}
+
+/// An entry of `schema_files`: a path whose kind follows from its
+/// extension, or a path with an explicit kind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SchemaFileEntry {
+    Path(String),
+    WithKind { path: String, kind: SchemaKind },
+}
+
+impl SchemaFileEntry {
+    pub fn path(&self) -> &str {
+        match self {
+            SchemaFileEntry::Path(path) | SchemaFileEntry::WithKind { path, .. } => path,
+        }
+    }
+
+    pub fn kind(&self) -> Option<SchemaKind> {
+        match self {
+            SchemaFileEntry::Path(path) => SchemaKind::from_extension(std::path::Path::new(path)),
+            SchemaFileEntry::WithKind { kind, .. } => Some(*kind),
+        }
+    }
+}
+
+/// A configured requirement that replaces the one from the schema: required
+/// everywhere (`true`), nowhere (`false`), or only in the listed profiles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequiredOverride {
+    Always(bool),
+    InProfiles(Vec<String>),
+}
+
+pub type Groups = IndexMap<String, GroupConfig>;
+
+/// How a group is presented: just a display title, or a title with a
+/// description.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GroupConfig {
+    Title(String),
+    Detailed {
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        description: Option<String>,
+    },
+}
+
+impl GroupConfig {
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            GroupConfig::Title(title) => Some(title),
+            GroupConfig::Detailed { title, .. } => title.as_deref(),
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            GroupConfig::Title(_) => None,
+            GroupConfig::Detailed { description, .. } => description.as_deref(),
+        }
+    }
+}
+
+/// Variables of one group, in the order they are presented.
+#[derive(Debug, Clone)]
+pub struct VariableGroup {
+    /// The group name used in schemas, or `Other` for ungrouped variables.
+    pub name: String,
+    /// The configured display title, or the name.
+    pub title: String,
+    pub description: Option<String>,
+    pub vars: Vec<EnvVar>,
+}
+
+/// Auto-discovery settings for each kind of schema.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiscoveryConfig {
+    #[serde(default)]
+    pub zod: KindDiscovery,
+    #[serde(default)]
+    pub pydantic: KindDiscovery,
+    #[serde(default)]
+    pub yaml: KindDiscovery,
+}
+
+impl DiscoveryConfig {
+    pub fn for_kind(&self, kind: SchemaKind) -> &KindDiscovery {
+        match kind {
+            SchemaKind::Zod => &self.zod,
+            SchemaKind::Pydantic => &self.pydantic,
+            SchemaKind::Yaml => &self.yaml,
+        }
+    }
+}
+
+/// Which files are auto-discovered as one kind of schema. `include` and
+/// `exclude` are globs relative to the workspace root; an empty `include`
+/// considers every file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KindDiscovery {
     #[serde(default = "default_true")]
-    pub auto_discover: bool,
+    pub enabled: bool,
+    #[serde(default)]
+    pub include: Vec<String>,
     #[serde(default)]
-    pub groups: HashMap<String, String>,
+    pub exclude: Vec<String>,
+}
+
+impl Default for KindDiscovery {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
 }
 
 fn default_true() -> bool {
@@ -54,7 +366,12 @@ impl Default for Config {
             schema_files: Vec::new(),
             env_files: vec![".env".to_string()],
             auto_discover: true,
-            groups: HashMap::new(),
+            groups: Groups::new(),
+            profiles: BTreeMap::new(),
+            required: BTreeMap::new(),
+            rules: Rules::default(),
+            exclude: Vec::new(),
+            discovery: DiscoveryConfig::default(),
         }
     }
 }
@@ -64,5 +381,6 @@ pub struct EnvFileEntry {
     pub name: String,
     pub value: Option<String>,
     pub line: usize,
+    pub column: usize,
     pub file_path: String,
 }
@@ -1,74 +1,497 @@
-use crate::types::{EnvVar, EnvFileEntry};
+use crate::types::{EnvType, EnvVar, EnvFileEntry, Groups, Rules, VariableGroup};
+use regex::Regex;
 use std::collections::HashMap;
+use tower_lsp::lsp_types::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationErrorKind {
+    Missing,
+    InvalidValue { value: String, reason: String },
+    Deprecated { replacement: Option<String> },
+    /// Required by a rule because `trigger` is set, or set to `value`. The
+    /// error's entry is the one of the trigger.
+    RequiredBy { trigger: String, value: Option<String> },
+    /// None of a `one_of` set is set.
+    NoneOf { vars: Vec<String> },
+    /// Set together with `others`, although at most one of them may be
+    /// (exactly one for `one_of`).
+    Conflicting { others: Vec<String>, exactly_one: bool },
+}
 
 #[derive(Debug, Clone)]
 pub struct ValidationError {
     pub var_name: String,
-    pub missing_from_all: bool,
+    pub kind: ValidationErrorKind,
     pub schema_var: EnvVar,
+    pub entry: Option<EnvFileEntry>,
+}
+
+impl ValidationError {
+    /// Whether the variable has to be added, either because the schema or
+    /// because a rule requires it.
+    pub fn is_missing(&self) -> bool {
+        matches!(self.kind, ValidationErrorKind::Missing | ValidationErrorKind::RequiredBy { .. })
+    }
 }
 
 #[derive(Debug)]
 pub struct ValidationResult {
     pub errors: Vec<ValidationError>,
+    pub warnings: Vec<ValidationError>,
     pub all_env_vars: HashMap<String, EnvFileEntry>,
     pub all_schema_vars: Vec<EnvVar>,
+    pub rules: Rules,
 }
 
-pub fn validate(schema_vars: &[EnvVar], env_entries: &[EnvFileEntry]) -> ValidationResult {
+/// Checks env entries against schema variables and the rules between them.
+/// `profile` is the profile being validated, which decides whether
+/// variables with `required_in` have to be set.
+pub fn validate(
+    schema_vars: &[EnvVar],
+    rules: &Rules,
+    env_entries: &[EnvFileEntry],
+    profile: Option<&str>,
+) -> ValidationResult {
     let env_map: HashMap<String, EnvFileEntry> = env_entries
         .iter()
         .map(|e| (e.name.clone(), e.clone()))
         .collect();
 
     let mut errors = Vec::new();
+    let mut warnings = Vec::new();
 
     for schema_var in schema_vars {
-        if schema_var.optional {
-            continue;
-        }
+        match env_map.get(&schema_var.name) {
+            Some(entry) => {
+                if schema_var.deprecated.is_some() {
+                    warnings.push(ValidationError {
+                        var_name: schema_var.name.clone(),
+                        kind: ValidationErrorKind::Deprecated {
+                            replacement: schema_var.replaced_by.clone(),
+                        },
+                        schema_var: schema_var.clone(),
+                        entry: Some(entry.clone()),
+                    });
+                }
 
-        if !env_map.contains_key(&schema_var.name) {
-            errors.push(ValidationError {
-                var_name: schema_var.name.clone(),
-                missing_from_all: true,
-                schema_var: schema_var.clone(),
-            });
+                let Some(value) = &entry.value else { continue };
+
+                if let Some(reason) = check_value(schema_var, value) {
+                    errors.push(ValidationError {
+                        var_name: schema_var.name.clone(),
+                        kind: ValidationErrorKind::InvalidValue {
+                            value: value.clone(),
+                            reason,
+                        },
+                        schema_var: schema_var.clone(),
+                        entry: Some(entry.clone()),
+                    });
+                }
+            }
+            None if schema_var.is_required_in(profile) => {
+                errors.push(ValidationError {
+                    var_name: schema_var.name.clone(),
+                    kind: ValidationErrorKind::Missing,
+                    schema_var: schema_var.clone(),
+                    entry: None,
+                });
+            }
+            None => {}
         }
     }
 
+    check_rules(schema_vars, rules, &env_map, profile, &mut errors);
+
     ValidationResult {
         errors,
+        warnings,
         all_env_vars: env_map,
         all_schema_vars: schema_vars.to_vec(),
+        rules: rules.clone(),
     }
 }
 
-pub fn group_variables(vars: &[EnvVar]) -> Vec<(String, Vec<EnvVar>)> {
-    let mut groups: HashMap<String, Vec<EnvVar>> = HashMap::new();
-    let mut ungrouped = Vec::new();
+/// Evaluates the requirements between variables. A variable counts as set
+/// when it has a non-empty value; a required variable is satisfied by any
+/// entry, as with schema requirements.
+fn check_rules(
+    schema_vars: &[EnvVar],
+    rules: &Rules,
+    env_map: &HashMap<String, EnvFileEntry>,
+    profile: Option<&str>,
+    errors: &mut Vec<ValidationError>,
+) {
+    let schema_var = |name: &str| {
+        schema_vars.iter().find(|v| v.name == name).cloned().unwrap_or_else(|| EnvVar {
+            name: name.to_string(),
+            ..Default::default()
+        })
+    };
+    let set_entry = |name: &String| env_map.get(name).filter(|e| e.value.as_deref().is_some_and(|v| !v.is_empty()));
 
-    for var in vars {
-        if let Some(group) = &var.group {
-            groups.entry(group.clone()).or_default().push(var.clone());
-        } else {
-            ungrouped.push(var.clone());
+    let mut require = |name: &String, trigger: &EnvFileEntry, value: Option<&str>| {
+        // Variables the schema requires anyway are already reported missing.
+        let reported = schema_vars.iter().any(|v| v.name == *name && v.is_required_in(profile));
+        if env_map.contains_key(name) || reported || errors.iter().any(|e| e.var_name == *name && e.is_missing()) {
+            return;
+        }
+        errors.push(ValidationError {
+            var_name: name.clone(),
+            kind: ValidationErrorKind::RequiredBy {
+                trigger: trigger.name.clone(),
+                value: value.map(String::from),
+            },
+            schema_var: schema_var(name),
+            entry: Some(trigger.clone()),
+        });
+    };
+
+    for (trigger, required) in &rules.requires {
+        let Some(entry) = set_entry(trigger) else { continue };
+        for name in required {
+            require(name, entry, None);
+        }
+    }
+
+    for (trigger, by_value) in &rules.requires_if {
+        let Some(entry) = set_entry(trigger) else { continue };
+        let value = entry.value.as_deref().unwrap_or_default();
+        for name in by_value.get(value).into_iter().flatten() {
+            require(name, entry, Some(value));
+        }
+    }
+
+    let exclusive_sets = rules
+        .one_of
+        .iter()
+        .map(|set| (set, true))
+        .chain(rules.mutually_exclusive.iter().map(|set| (set, false)));
+
+    for (set, exactly_one) in exclusive_sets {
+        let present: Vec<&EnvFileEntry> = set.iter().filter_map(set_entry).collect();
+
+        if present.is_empty() && exactly_one && !set.is_empty() {
+            errors.push(ValidationError {
+                var_name: set.join(", "),
+                kind: ValidationErrorKind::NoneOf { vars: set.clone() },
+                schema_var: schema_var(&set[0]),
+                entry: None,
+            });
+        }
+        if present.len() < 2 {
+            continue;
         }
+
+        for entry in &present {
+            let others = present
+                .iter()
+                .filter(|other| other.name != entry.name)
+                .map(|other| other.name.clone())
+                .collect();
+            errors.push(ValidationError {
+                var_name: entry.name.clone(),
+                kind: ValidationErrorKind::Conflicting { others, exactly_one },
+                schema_var: schema_var(&entry.name),
+                entry: Some((*entry).clone()),
+            });
+        }
+    }
+}
+
+impl ValidationResult {
+    /// Env entries whose variable is neither declared in any schema nor
+    /// mentioned by a rule.
+    ///
+    /// Empty when no schema variables are known, since every entry would be
+    /// reported otherwise.
+    pub fn unused_entries(&self) -> Vec<&EnvFileEntry> {
+        if self.all_schema_vars.is_empty() {
+            return Vec::new();
+        }
+
+        let mut unused: Vec<_> = self
+            .all_env_vars
+            .values()
+            .filter(|e| !self.all_schema_vars.iter().any(|v| v.name == e.name) && !self.rules.mentions(&e.name))
+            .collect();
+        unused.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+        unused
+    }
+}
+
+/// Checks a value against the type and constraints declared in the schema.
+///
+/// Returns a human-readable reason when the value is rejected.
+fn check_value(var: &EnvVar, value: &str) -> Option<String> {
+    let number = match var.var_type {
+        EnvType::Integer => match value.parse::<i64>() {
+            Ok(n) => Some(n as f64),
+            Err(_) => return Some("expected an integer".to_string()),
+        },
+        EnvType::Number => match value.parse::<f64>() {
+            Ok(n) => Some(n),
+            Err(_) => return Some("expected a number".to_string()),
+        },
+        EnvType::Boolean => {
+            if !matches!(value.to_lowercase().as_str(), "true" | "false" | "1" | "0" | "yes" | "no") {
+                return Some("expected a boolean".to_string());
+            }
+            None
+        }
+        EnvType::Url => {
+            if Url::parse(value).is_err() {
+                return Some("expected a URL".to_string());
+            }
+            None
+        }
+        EnvType::String => None,
+    };
+
+    if !var.allowed_values.is_empty() && !var.allowed_values.iter().any(|v| v == value) {
+        return Some(format!("expected one of: {}", var.allowed_values.join(", ")));
+    }
+
+    if let Some(pattern) = &var.pattern {
+        if let Ok(regex) = Regex::new(&format!("^(?:{})$", pattern)) {
+            if !regex.is_match(value) {
+                return Some(format!("does not match pattern `{}`", pattern));
+            }
+        }
+    }
+
+    let measured = number.unwrap_or(value.chars().count() as f64);
+    let unit = if number.is_some() { "" } else { " characters" };
+
+    if let Some(min) = var.min {
+        if measured < min {
+            return Some(format!("must be at least {}{}", min, unit));
+        }
+    }
+
+    if let Some(max) = var.max {
+        if measured > max {
+            return Some(format!("must be at most {}{}", max, unit));
+        }
+    }
+
+    None
+}
+
+/// Splits variables into groups, ungrouped ones going to `Other`.
+///
+/// Groups listed in the `groups` configuration come first, in configured
+/// order and with their title and description; the rest follow sorted by
+/// name. Variables within a group are sorted by name.
+pub fn group_variables(vars: &[EnvVar], groups: &Groups) -> Vec<VariableGroup> {
+    let mut by_name: HashMap<String, Vec<EnvVar>> = HashMap::new();
+
+    for var in vars {
+        let name = var.group.clone().unwrap_or_else(|| "Other".to_string());
+        by_name.entry(name).or_default().push(var.clone());
     }
 
-    let mut result: Vec<(String, Vec<EnvVar>)> = groups
+    let mut result: Vec<VariableGroup> = by_name
         .into_iter()
-        .map(|(k, mut v)| {
-            v.sort_by(|a, b| a.name.cmp(&b.name));
-            (k, v)
+        .map(|(name, mut vars)| {
+            vars.sort_by(|a, b| a.name.cmp(&b.name));
+            let config = groups.get(&name);
+            VariableGroup {
+                title: config.and_then(|c| c.title()).unwrap_or(&name).to_string(),
+                description: config.and_then(|c| c.description()).map(String::from),
+                name,
+                vars,
+            }
         })
         .collect();
 
-    if !ungrouped.is_empty() {
-        ungrouped.sort_by(|a, b| a.name.cmp(&b.name));
-        result.push(("Other".to_string(), ungrouped));
+    result.sort_by(|a, b| {
+        let rank = |group: &VariableGroup| groups.get_index_of(&group.name).unwrap_or(usize::MAX);
+        rank(a).cmp(&rank(b)).then_with(|| a.name.cmp(&b.name))
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn var(name: &str, required: bool) -> EnvVar {
+        EnvVar {
+            name: name.to_string(),
+            optional: !required,
+            ..Default::default()
+        }
     }
 
-    result.sort_by(|a, b| a.0.cmp(&b.0));
-    result
+    fn entries(pairs: &[(&str, &str)]) -> Vec<EnvFileEntry> {
+        pairs
+            .iter()
+            .enumerate()
+            .map(|(line, (name, value))| EnvFileEntry {
+                name: name.to_string(),
+                value: Some(value.to_string()),
+                line,
+                column: 0,
+                file_path: "/project/.env".to_string(),
+            })
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn requires(trigger: &str, required: &[&str]) -> Rules {
+        Rules {
+            requires: BTreeMap::from([(trigger.to_string(), names(required))]),
+            ..Default::default()
+        }
+    }
+
+    fn requires_if(trigger: &str, value: &str, required: &[&str]) -> Rules {
+        Rules {
+            requires_if: BTreeMap::from([(
+                trigger.to_string(),
+                BTreeMap::from([(value.to_string(), names(required))]),
+            )]),
+            ..Default::default()
+        }
+    }
+
+    fn one_of(set: &[&str]) -> Rules {
+        Rules {
+            one_of: vec![names(set)],
+            ..Default::default()
+        }
+    }
+
+    fn mutually_exclusive(set: &[&str]) -> Rules {
+        Rules {
+            mutually_exclusive: vec![names(set)],
+            ..Default::default()
+        }
+    }
+
+    fn errors(rules: &Rules, pairs: &[(&str, &str)]) -> Vec<(String, ValidationErrorKind)> {
+        validate(&[], rules, &entries(pairs), None)
+            .errors
+            .into_iter()
+            .map(|e| (e.var_name, e.kind))
+            .collect()
+    }
+
+    fn required_by(trigger: &str, value: Option<&str>) -> ValidationErrorKind {
+        ValidationErrorKind::RequiredBy {
+            trigger: trigger.to_string(),
+            value: value.map(String::from),
+        }
+    }
+
+    #[test]
+    fn requires_is_satisfied_by_the_required_variables() {
+        let rules = requires("SMTP_HOST", &["SMTP_USER", "SMTP_PASSWORD"]);
+
+        assert!(errors(&rules, &[("SMTP_HOST", "mail"), ("SMTP_USER", "me"), ("SMTP_PASSWORD", "")]).is_empty());
+        assert!(errors(&rules, &[("SMTP_USER", "me")]).is_empty());
+        assert!(errors(&rules, &[("SMTP_HOST", "")]).is_empty());
+    }
+
+    #[test]
+    fn requires_reports_missing_variables_on_the_trigger() {
+        let result = validate(&[], &requires("SMTP_HOST", &["SMTP_USER"]), &entries(&[("A", "1"), ("SMTP_HOST", "mail")]), None);
+
+        assert_eq!(result.errors.len(), 1);
+        let error = &result.errors[0];
+        assert_eq!(error.var_name, "SMTP_USER");
+        assert_eq!(error.kind, required_by("SMTP_HOST", None));
+        assert!(error.is_missing());
+        assert_eq!(error.entry.as_ref().map(|e| e.line), Some(1));
+    }
+
+    #[test]
+    fn requires_if_only_applies_to_the_matching_value() {
+        let rules = requires_if("AUTH_PROVIDER", "oidc", &["OIDC_ISSUER", "OIDC_CLIENT_ID"]);
+
+        assert!(errors(&rules, &[("AUTH_PROVIDER", "basic")]).is_empty());
+        assert!(errors(&rules, &[("AUTH_PROVIDER", "oidc"), ("OIDC_ISSUER", "x"), ("OIDC_CLIENT_ID", "y")]).is_empty());
+        assert_eq!(
+            errors(&rules, &[("AUTH_PROVIDER", "oidc"), ("OIDC_ISSUER", "x")]),
+            vec![("OIDC_CLIENT_ID".to_string(), required_by("AUTH_PROVIDER", Some("oidc")))]
+        );
+    }
+
+    #[test]
+    fn rule_requirements_are_reported_once() {
+        let mut rules = requires("A", &["C"]);
+        rules.extend(&requires("B", &["C"]));
+        let schema = [var("C", true)];
+
+        let result = validate(&[], &rules, &entries(&[("A", "1"), ("B", "1")]), None);
+        assert_eq!(result.errors.len(), 1);
+
+        // A variable the schema requires is reported as missing instead.
+        let result = validate(&schema, &rules, &entries(&[("A", "1")]), None);
+        let kinds: Vec<_> = result.errors.iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(kinds, vec![ValidationErrorKind::Missing]);
+    }
+
+    #[test]
+    fn one_of_needs_exactly_one_set_variable() {
+        let rules = one_of(&["DATABASE_URL", "DB_HOST"]);
+
+        assert!(errors(&rules, &[("DATABASE_URL", "postgres://")]).is_empty());
+        assert!(errors(&rules, &[("DB_HOST", "db"), ("DATABASE_URL", "")]).is_empty());
+
+        assert_eq!(
+            errors(&rules, &[("DATABASE_URL", "")]),
+            vec![(
+                "DATABASE_URL, DB_HOST".to_string(),
+                ValidationErrorKind::NoneOf { vars: names(&["DATABASE_URL", "DB_HOST"]) }
+            )]
+        );
+
+        assert_eq!(
+            errors(&rules, &[("DATABASE_URL", "postgres://"), ("DB_HOST", "db")]),
+            vec![
+                (
+                    "DATABASE_URL".to_string(),
+                    ValidationErrorKind::Conflicting { others: names(&["DB_HOST"]), exactly_one: true }
+                ),
+                (
+                    "DB_HOST".to_string(),
+                    ValidationErrorKind::Conflicting { others: names(&["DATABASE_URL"]), exactly_one: true }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn mutually_exclusive_allows_at_most_one_set_variable() {
+        let rules = mutually_exclusive(&["A", "B", "C"]);
+
+        assert!(errors(&rules, &[]).is_empty());
+        assert!(errors(&rules, &[("B", "1"), ("C", "")]).is_empty());
+
+        let conflicts = errors(&rules, &[("A", "1"), ("B", "1"), ("C", "1")]);
+        assert_eq!(conflicts.len(), 3);
+        assert_eq!(
+            conflicts[1],
+            (
+                "B".to_string(),
+                ValidationErrorKind::Conflicting { others: names(&["A", "C"]), exactly_one: false }
+            )
+        );
+    }
+
+    #[test]
+    fn variables_mentioned_by_rules_are_not_unused() {
+        let schema = [var("AUTH_PROVIDER", true)];
+        let rules = requires_if("AUTH_PROVIDER", "oidc", &["OIDC_ISSUER"]);
+        let result = validate(&schema, &rules, &entries(&[("AUTH_PROVIDER", "oidc"), ("OIDC_ISSUER", "x"), ("OTHER", "1")]), None);
+
+        assert!(result.errors.is_empty());
+        let unused: Vec<_> = result.unused_entries().iter().map(|e| e.name.clone()).collect();
+        assert_eq!(unused, vec!["OTHER"]);
+    }
 }
@@ -0,0 +1,92 @@
+use crate::git::{self, IgnoreRules};
+use glob::{MatchOptions, Pattern};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Directories that never contain a project's own schemas or env files.
+pub const DEFAULT_EXCLUDED_DIRS: &[&str] = &[".git", "node_modules", ".venv", "venv", "__pycache__"];
+
+pub const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Lists the files below `root`, skipping dependency directories, anything
+/// ignored by `.gitignore` and paths matching one of the `exclude` globs
+/// (relative to `root`). Files are returned sorted by path.
+pub fn walk_files(root: &Path, exclude: &[String]) -> Vec<PathBuf> {
+    let exclude: Vec<Pattern> = exclude.iter().filter_map(|p| Pattern::new(p).ok()).collect();
+
+    // Ignore rules that apply inside each visited directory, built up from
+    // the rules of its parent.
+    let mut rules: HashMap<PathBuf, IgnoreRules> = HashMap::new();
+    let root_rules = match git::find_repo_root(root) {
+        Some(repo_root) => IgnoreRules::for_directory(&repo_root, root),
+        None => {
+            let mut rules = IgnoreRules::new();
+            rules.add_file(&root.join(".gitignore"), root);
+            rules
+        }
+    };
+    rules.insert(root.to_path_buf(), root_rules);
+
+    let walker = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let path = entry.path();
+            if path == root {
+                return true;
+            }
+            let is_dir = entry.file_type().is_dir();
+
+            if is_dir && entry.file_name().to_str().is_some_and(|n| DEFAULT_EXCLUDED_DIRS.contains(&n)) {
+                return false;
+            }
+            if let Ok(relative) = path.strip_prefix(root) {
+                if exclude.iter().any(|p| p.matches_path_with(relative, MATCH_OPTIONS)) {
+                    return false;
+                }
+            }
+
+            let Some(parent_rules) = path.parent().and_then(|parent| rules.get(parent)) else {
+                return true;
+            };
+            if parent_rules.is_ignored(path, is_dir) {
+                return false;
+            }
+
+            if is_dir {
+                let mut dir_rules = parent_rules.clone();
+                dir_rules.add_file(&path.join(".gitignore"), path);
+                rules.insert(path.to_path_buf(), dir_rules);
+            }
+            true
+        });
+
+    walker
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+/// Picks the files matching `patterns` (relative to `root`), in pattern
+/// order and without duplicates.
+pub fn match_files<S: AsRef<str>>(files: &[PathBuf], root: &Path, patterns: &[S]) -> Vec<String> {
+    let mut matched: Vec<String> = Vec::new();
+
+    for pattern in patterns.iter().filter_map(|p| Pattern::new(p.as_ref()).ok()) {
+        for file in files {
+            let Ok(relative) = file.strip_prefix(root) else { continue };
+            let file = file.to_string_lossy().to_string();
+            if pattern.matches_path_with(relative, MATCH_OPTIONS) && !matched.contains(&file) {
+                matched.push(file);
+            }
+        }
+    }
+
+    matched
+}
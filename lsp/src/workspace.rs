@@ -0,0 +1,272 @@
+use crate::config;
+use crate::drift::{self, SchemaConflict};
+use crate::env_file;
+use crate::schema;
+use crate::types::{Config, EnvFileEntry, EnvVar, ParsedSchema, Rules, SchemaSource};
+use crate::walk;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::Url;
+use tracing::{error, info};
+
+/// Files that mark the root of a package in a monorepo.
+pub const PACKAGE_MARKERS: &[&str] = &[
+    ".envchecker.json",
+    ".envchecker.toml",
+    ".envchecker.yaml",
+    ".envchecker.yml",
+    "package.json",
+    "pyproject.toml",
+];
+
+/// A discovered schema source and the result of parsing it.
+pub type CachedSchema = (SchemaSource, Option<ParsedSchema>);
+
+/// A directory with its own configuration: a workspace folder, or a package
+/// inside one that has its own configuration.
+#[derive(Debug, Default)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub config: Config,
+    /// Roots of nested workspaces, whose files belong to them instead.
+    pub nested_roots: Vec<PathBuf>,
+    /// Every discovered schema source with its last parse result, so a file
+    /// change only re-parses the sources it touches.
+    pub parsed_schemas: Vec<CachedSchema>,
+    pub conflicts: Vec<SchemaConflict>,
+    pub env_files: HashMap<String, Vec<EnvFileEntry>>,
+}
+
+impl Workspace {
+    pub fn new(root: PathBuf, nested_roots: Vec<PathBuf>) -> Self {
+        let mut workspace = Self {
+            root,
+            nested_roots,
+            ..Default::default()
+        };
+        workspace.reload_config();
+        workspace
+    }
+
+    pub fn reload_config(&mut self) {
+        self.config = match config::load_config(&self.root) {
+            Ok(Some(config)) => {
+                info!("Loaded configuration for {}", self.root.display());
+                config
+            }
+            Ok(None) => Config::default(),
+            Err(e) => {
+                error!("Failed to load configuration: {:?}", e);
+                Config::default()
+            }
+        };
+    }
+
+    /// Whether `path` belongs to this workspace rather than a nested one.
+    pub fn owns(&self, path: &Path) -> bool {
+        path.starts_with(&self.root) && !self.nested_roots.iter().any(|nested| path.starts_with(nested))
+    }
+
+    /// Re-discovers schema sources and re-parses them.
+    ///
+    /// With `changed` set, only sources at those paths are parsed again and
+    /// the rest come from the previous run. A changed YAML file re-parses all
+    /// YAML schemas, since any of them may extend it.
+    pub fn reload_schemas(&mut self, changed: Option<&HashSet<PathBuf>>) {
+        let schema_sources = match config::discover_schemas(&self.root, &self.config) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("Failed to discover schemas: {:?}", e);
+                return;
+            }
+        };
+
+        let yaml_changed = changed.is_some_and(|paths| {
+            paths
+                .iter()
+                .any(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("yml") | Some("yaml")))
+        });
+        let previous = std::mem::take(&mut self.parsed_schemas);
+
+        for source in schema_sources {
+            if !self.owns(Path::new(source.file_path())) {
+                continue;
+            }
+
+            let stale = match changed {
+                None => true,
+                Some(paths) => {
+                    paths.contains(Path::new(source.file_path()))
+                        || (yaml_changed && matches!(source, SchemaSource::Yaml { .. }))
+                }
+            };
+            let cached = previous.iter().find(|(s, _)| *s == source).filter(|_| !stale);
+            let parsed = match cached {
+                Some((_, parsed)) => parsed.clone(),
+                None => schema::parse_schema_or_warn(&source),
+            };
+            self.parsed_schemas.push((source, parsed));
+        }
+
+        let schemas: Vec<ParsedSchema> = self.schemas().cloned().collect();
+        self.conflicts = find_package_conflicts(&schemas, &self.root);
+
+        info!(
+            "Loaded {} schemas in {} ({} cross-schema conflicts)",
+            schemas.len(),
+            self.root.display(),
+            self.conflicts.len()
+        );
+    }
+
+    pub fn load_env_files(&mut self) {
+        let env_file_paths = match config::get_env_file_paths(&self.root, &self.config) {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Failed to get env file paths: {:?}", e);
+                return;
+            }
+        };
+
+        let mut env_files = HashMap::new();
+
+        for path in env_file_paths {
+            if !self.owns(Path::new(&path)) {
+                continue;
+            }
+            let Ok(uri) = Url::from_file_path(&path) else { continue };
+            if let Ok(entries) = env_file::parse_env_file(Path::new(&path)) {
+                env_files.insert(uri.to_string(), entries);
+            }
+        }
+
+        info!("Loaded {} .env files in {}", env_files.len(), self.root.display());
+        self.env_files = env_files;
+    }
+
+    pub fn schemas(&self) -> impl Iterator<Item = &ParsedSchema> {
+        self.parsed_schemas.iter().filter_map(|(_, parsed)| parsed.as_ref())
+    }
+
+    /// The merged variables of the schemas that apply to `file_path`.
+    pub fn schema_vars_for(&self, file_path: &Path) -> Vec<EnvVar> {
+        let schemas: Vec<ParsedSchema> = self.schemas().cloned().collect();
+        let scoped: Vec<ParsedSchema> = scoped_schemas(&schemas, file_path, &self.root)
+            .into_iter()
+            .cloned()
+            .collect();
+        let mut vars = schema::merge_schemas(&scoped);
+        config::apply_required_overrides(&self.config, &mut vars);
+        vars
+    }
+
+    /// The rules of the schemas that apply to `file_path`, together with
+    /// those of the configuration.
+    pub fn rules_for(&self, file_path: &Path) -> Rules {
+        let schemas: Vec<ParsedSchema> = self.schemas().cloned().collect();
+        let scoped: Vec<ParsedSchema> = scoped_schemas(&schemas, file_path, &self.root)
+            .into_iter()
+            .cloned()
+            .collect();
+        let mut rules = schema::merge_rules(&scoped);
+        rules.extend(&self.config.rules);
+        rules
+    }
+
+    /// Entries of the loaded env files in the same package as `file_path`.
+    pub fn package_env_entries(&self, file_path: &Path) -> Vec<EnvFileEntry> {
+        let package = package_root(file_path, &self.root);
+
+        self.env_files
+            .values()
+            .flatten()
+            .filter(|entry| package_root(Path::new(&entry.file_path), &self.root) == package)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Finds the workspace a path belongs to: the one with the longest root
+/// containing it.
+pub fn workspace_for<'a>(workspaces: &'a [Workspace], path: &Path) -> Option<&'a Workspace> {
+    workspaces
+        .iter()
+        .filter(|w| path.starts_with(&w.root))
+        .max_by_key(|w| w.root.components().count())
+}
+
+/// Returns `folder` and every directory below it with configuration of its
+/// own, shallowest first.
+pub fn find_config_roots(folder: &Path) -> Vec<PathBuf> {
+    let mut roots = vec![folder.to_path_buf()];
+    let files = walk::walk_files(folder, &[]);
+    let patterns: Vec<String> = PACKAGE_MARKERS.iter().map(|marker| format!("**/{}", marker)).collect();
+
+    for marker in walk::match_files(&files, folder, &patterns) {
+        if let Some(dir) = Path::new(&marker).parent() {
+            if !roots.iter().any(|r| r == dir) && config::has_project_config(dir) {
+                roots.push(dir.to_path_buf());
+            }
+        }
+    }
+
+    roots.sort_by_key(|r| r.components().count());
+    roots
+}
+
+/// The nearest directory at or above `path` that contains a package marker,
+/// or `root` when there is none below it.
+pub fn package_root(path: &Path, root: &Path) -> PathBuf {
+    let start = if path.is_dir() { path } else { path.parent().unwrap_or(path) };
+
+    start
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root) && *dir != root)
+        .find(|dir| PACKAGE_MARKERS.iter().any(|marker| dir.join(marker).is_file()))
+        .map_or_else(|| root.to_path_buf(), Path::to_path_buf)
+}
+
+/// The schemas an env file is validated against: those in its nearest
+/// package, or in the closest enclosing package that has schemas, up to
+/// the schemas at the workspace root.
+///
+/// Schemas of sibling or nested packages never apply, so a file whose
+/// packages have no schemas is not validated against anything.
+pub fn scoped_schemas<'a>(schemas: &'a [ParsedSchema], file_path: &Path, root: &Path) -> Vec<&'a ParsedSchema> {
+    let schema_packages: Vec<PathBuf> = schemas
+        .iter()
+        .map(|s| package_root(Path::new(s.source.file_path()), root))
+        .collect();
+
+    let mut package = package_root(file_path, root);
+    loop {
+        let scoped: Vec<&ParsedSchema> = schemas
+            .iter()
+            .zip(&schema_packages)
+            .filter(|(_, p)| **p == package)
+            .map(|(s, _)| s)
+            .collect();
+
+        if !scoped.is_empty() {
+            return scoped;
+        }
+        match package.parent().filter(|_| package != root) {
+            Some(parent) => package = package_root(parent, root),
+            None => return Vec::new(),
+        }
+    }
+}
+
+/// Reports conflicts only between schemas of the same package, since
+/// packages are validated independently.
+pub fn find_package_conflicts(schemas: &[ParsedSchema], root: &Path) -> Vec<SchemaConflict> {
+    let mut by_package: BTreeMap<PathBuf, Vec<ParsedSchema>> = BTreeMap::new();
+    for schema in schemas {
+        by_package
+            .entry(package_root(Path::new(schema.source.file_path()), root))
+            .or_default()
+            .push(schema.clone());
+    }
+
+    by_package.values().flat_map(|schemas| drift::find_conflicts(schemas)).collect()
+}